@@ -0,0 +1,284 @@
+use std::fmt::Write as FmtWrite;
+use std::io::{self, Write};
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use iron::headers::{ContentEncoding, Range};
+use iron::method::Method;
+use iron::prelude::*;
+use iron::response::WriteBody;
+use iron::status::Status;
+use iron::typemap::Key;
+use mount::OriginalUrl;
+
+use served_file::ServedFile;
+use time::{self, Timespec};
+
+/// Receives an `AccessLogEntry` for every request a `Static` answers, once the response has
+/// been sent.
+///
+/// Closures taking an `&AccessLogEntry` are access logs, and `LogWriter` writes entries as
+/// lines of text.
+pub trait AccessLog: Send + Sync + 'static {
+    /// Record a request.
+    fn log(&self, entry: &AccessLogEntry);
+}
+
+impl<F> AccessLog for F where F: Fn(&AccessLogEntry) + Send + Sync + 'static {
+    fn log(&self, entry: &AccessLogEntry) {
+        self(entry)
+    }
+}
+
+/// A request answered by `Static`, and how it was answered.
+#[derive(Clone, Debug)]
+pub struct AccessLogEntry {
+    /// When the request was received.
+    pub time: SystemTime,
+    /// The address of the client.
+    pub remote_addr: SocketAddr,
+    /// The request method.
+    pub method: Method,
+    /// The requested path and query, as sent by the client before any `Mount` stripped a
+    /// prefix.
+    pub url: String,
+    /// The HTTP version of the request, such as `HTTP/1.1`.
+    pub version: String,
+    /// The file that was served, if any.
+    pub path: Option<PathBuf>,
+    /// The status of the response, including error responses.
+    pub status: Status,
+    /// The number of bytes of the body that were sent, after compression.
+    pub bytes: u64,
+    /// The length of the file's body before compression, if the response carried it.
+    pub length: Option<u64>,
+    /// The `Range` header of the request, if it had one. Files are always served whole.
+    pub range: Option<String>,
+    /// The `Content-Encoding` the body was sent with, such as `gzip`.
+    pub encoding: Option<String>,
+    /// Whether the client's cached copy was current, and the response was a
+    /// `304 Not Modified`.
+    pub not_modified: bool,
+    /// Whether the body was served from an existing `MmapCache` mapping, or `None` if it
+    /// wasn't served from memory at all.
+    pub cache_hit: Option<bool>,
+    /// The time from receiving the request until the last byte of the body was written.
+    pub latency: Duration,
+}
+
+impl AccessLogEntry {
+    /// Format the entry in the Common Log Format used by Apache and nginx, which leaves out
+    /// the fields it has no place for.
+    ///
+    /// `127.0.0.1 - - [19/Oct/2026:13:55:36 +0200] "GET /index.html HTTP/1.1" 200 2326`
+    pub fn common_log_format(&self) -> String {
+        let time = time::at(timespec(self.time));
+        let bytes = if self.bytes == 0 { "-".to_owned() } else { self.bytes.to_string() };
+        format!("{} - - [{}] \"{} {} {}\" {} {}",
+                self.remote_addr.ip(),
+                time.strftime("%d/%b/%Y:%H:%M:%S %z").unwrap(),
+                self.method, self.url, self.version,
+                self.status.to_u16(), bytes)
+    }
+
+    /// Format the entry as a single-line JSON object with every field.
+    pub fn json(&self) -> String {
+        let path = self.path.as_ref().map(|path| path.to_string_lossy().into_owned());
+        let mut json = String::from("{");
+        let _ = write!(json, "\"time\":{},", json_string(&time::at_utc(timespec(self.time)).rfc3339().to_string()));
+        let _ = write!(json, "\"remote_addr\":{},", json_string(&self.remote_addr.ip().to_string()));
+        let _ = write!(json, "\"method\":{},", json_string(self.method.as_ref()));
+        let _ = write!(json, "\"url\":{},", json_string(&self.url));
+        let _ = write!(json, "\"version\":{},", json_string(&self.version));
+        let _ = write!(json, "\"path\":{},", json_option(path.as_ref()));
+        let _ = write!(json, "\"status\":{},", self.status.to_u16());
+        let _ = write!(json, "\"bytes\":{},", self.bytes);
+        let _ = write!(json, "\"length\":{},", self.length.map_or_else(|| "null".to_owned(), |length| length.to_string()));
+        let _ = write!(json, "\"range\":{},", json_option(self.range.as_ref()));
+        let _ = write!(json, "\"encoding\":{},", json_option(self.encoding.as_ref()));
+        let _ = write!(json, "\"not_modified\":{},", self.not_modified);
+        let _ = write!(json, "\"cache_hit\":{},", self.cache_hit.map_or_else(|| "null".to_owned(), |hit| hit.to_string()));
+        let _ = write!(json, "\"latency_ms\":{:.3}", self.latency.as_secs_f64() * 1000.0);
+        json.push('}');
+        json
+    }
+}
+
+/// The line format of a `LogWriter`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LogFormat {
+    /// `AccessLogEntry::common_log_format`.
+    Common,
+    /// `AccessLogEntry::json`.
+    Json,
+}
+
+/// An `AccessLog` writing a line per request, such as to standard output or a file.
+///
+/// ## Example
+///
+/// ```ignore
+/// let file = OpenOptions::new().create(true).append(true).open("access.log")?;
+/// let static_handler = Static::new(path).access_log(LogWriter::new(LogFormat::Json, file));
+/// ```
+pub struct LogWriter<W> {
+    format: LogFormat,
+    out: Mutex<W>,
+}
+
+impl<W: Write + Send + 'static> LogWriter<W> {
+    /// Write lines in `format` to `out`.
+    pub fn new(format: LogFormat, out: W) -> LogWriter<W> {
+        LogWriter { format, out: Mutex::new(out) }
+    }
+}
+
+impl LogWriter<io::Stdout> {
+    /// Write lines in `format` to standard output.
+    pub fn stdout(format: LogFormat) -> LogWriter<io::Stdout> {
+        LogWriter::new(format, io::stdout())
+    }
+}
+
+impl<W: Write + Send + 'static> AccessLog for LogWriter<W> {
+    fn log(&self, entry: &AccessLogEntry) {
+        let line = match self.format {
+            LogFormat::Common => entry.common_log_format(),
+            LogFormat::Json => entry.json(),
+        };
+        let mut out = self.out.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        // A log that can't be written must not take the server down with it.
+        let _ = writeln!(out, "{}", line);
+    }
+}
+
+/// How `Static` served a file, kept in the response's extensions for the access log next to
+/// the `ServedFile`.
+pub(crate) struct Served;
+
+impl Key for Served {
+    type Value = ServedFrom;
+}
+
+pub(crate) struct ServedFrom {
+    pub length: Option<u64>,
+    pub cache_hit: Option<bool>,
+}
+
+/// Record the request in every one of `logs` once `response` has been sent.
+pub(crate) fn attach(logs: Vec<Arc<dyn AccessLog>>, req: &Request, started: Instant, time: SystemTime,
+                     response: &mut Response) {
+    let url = req.extensions.get::<OriginalUrl>().unwrap_or(&req.url);
+    let url = match url.query() {
+        Some(query) => format!("/{}?{}", url.path().join("/"), query),
+        None => format!("/{}", url.path().join("/")),
+    };
+    let served = response.extensions.get::<Served>();
+    let status = response.status.unwrap_or(Status::NotFound);
+
+    let entry = AccessLogEntry {
+        time,
+        remote_addr: req.remote_addr,
+        method: req.method.clone(),
+        url,
+        version: req.version.to_string(),
+        path: response.extensions.get::<ServedFile>().map(|file| file.path.clone()),
+        status,
+        bytes: 0,
+        length: served.and_then(|served| served.length),
+        range: req.headers.get::<Range>().map(|range| range.to_string()),
+        encoding: response.headers.get::<ContentEncoding>().map(|encoding| encoding.to_string()),
+        not_modified: status == Status::NotModified,
+        cache_hit: served.and_then(|served| served.cache_hit),
+        latency: Duration::default(),
+    };
+
+    match response.body.take() {
+        Some(body) => {
+            response.body = Some(Box::new(LoggedBody { body, logs, entry, started }));
+        },
+        // Nothing is left to send.
+        None => {
+            let mut entry = entry;
+            entry.latency = started.elapsed();
+            for log in &logs {
+                log.log(&entry);
+            }
+        },
+    }
+}
+
+/// A body that counts the bytes written, and logs the request once it is done, or dropped
+/// without being written.
+struct LoggedBody {
+    body: Box<dyn WriteBody>,
+    logs: Vec<Arc<dyn AccessLog>>,
+    entry: AccessLogEntry,
+    started: Instant,
+}
+
+impl WriteBody for LoggedBody {
+    fn write_body(&mut self, res: &mut dyn Write) -> io::Result<()> {
+        let mut counted = Counted { inner: res, bytes: 0 };
+        let result = self.body.write_body(&mut counted);
+        self.entry.bytes += counted.bytes;
+        result
+    }
+}
+
+impl Drop for LoggedBody {
+    fn drop(&mut self) {
+        self.entry.latency = self.started.elapsed();
+        for log in &self.logs {
+            log.log(&self.entry);
+        }
+    }
+}
+
+struct Counted<'a> {
+    inner: &'a mut dyn Write,
+    bytes: u64,
+}
+
+impl<'a> Write for Counted<'a> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let written = self.inner.write(buf)?;
+        self.bytes += written as u64;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+fn timespec(time: SystemTime) -> Timespec {
+    let since_epoch = time.duration_since(UNIX_EPOCH).unwrap_or_default();
+    Timespec::new(since_epoch.as_secs() as i64, since_epoch.subsec_nanos() as i32)
+}
+
+fn json_option(value: Option<&String>) -> String {
+    value.map_or_else(|| "null".to_owned(), |value| json_string(value))
+}
+
+fn json_string(value: &str) -> String {
+    let mut json = String::with_capacity(value.len() + 2);
+    json.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            '\n' => json.push_str("\\n"),
+            '\r' => json.push_str("\\r"),
+            '\t' => json.push_str("\\t"),
+            c if (c as u32) < 0x20 => {
+                let _ = write!(json, "\\u{:04x}", c as u32);
+            },
+            c => json.push(c),
+        }
+    }
+    json.push('"');
+    json
+}
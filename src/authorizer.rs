@@ -0,0 +1,66 @@
+use std::error::Error;
+use std::fmt;
+use std::path::Path;
+
+use iron::prelude::*;
+
+/// Decides whether requests may open files, for a `Static` given to `Static::authorizer`.
+///
+/// It is asked with the path of the file relative to the root, after `.` and `..` segments
+/// have been resolved and percent-escapes decoded, so the decision is about the file that
+/// would actually be served however its URL was written. It is asked again for the index file
+/// or fallback a request ends up with, may be asked about paths that don't exist, and is asked
+/// about every entry of a directory listing, which leaves out what isn't allowed.
+///
+/// The path keeps the case of the URL, which on filesystems that ignore case may differ from
+/// the name of the file, so such authorizers should compare paths without case.
+///
+/// Closures taking the `&Request` and the `&Path` are authorizers.
+///
+/// ## Example
+///
+/// ```ignore
+/// let site = Static::new("public").authorizer(|req: &Request, path: &Path| {
+///     if !path.starts_with("account") || req.extensions.get::<Session>().is_some() {
+///         Access::Allow
+///     } else {
+///         Access::Redirect("/login".to_owned())
+///     }
+/// });
+/// ```
+pub trait Authorizer: Send + Sync + 'static {
+    /// Whether `req` may open the file at `path`, relative to the root.
+    fn authorize(&self, req: &Request, path: &Path) -> Access;
+}
+
+impl<F> Authorizer for F where F: Fn(&Request, &Path) -> Access + Send + Sync + 'static {
+    fn authorize(&self, req: &Request, path: &Path) -> Access {
+        self(req, path)
+    }
+}
+
+/// The decision of an `Authorizer`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Access {
+    /// Serve the file.
+    Allow,
+    /// Answer with `403 Forbidden`.
+    Deny,
+    /// Answer with `302 Found`, redirecting to a URL or absolute path such as a login page.
+    Redirect(String),
+}
+
+/// Thrown if an `Authorizer` denies a request. It is always accompanied by a Forbidden
+/// response.
+#[derive(Debug)]
+pub struct Forbidden;
+
+impl Error for Forbidden {
+    fn description(&self) -> &str { "Forbidden" }
+}
+
+impl fmt::Display for Forbidden {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("Forbidden")
+    }
+}
@@ -0,0 +1,62 @@
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use url::percent_encoding::{utf8_percent_encode, PATH_SEGMENT_ENCODE_SET};
+
+/// Render an HTML listing of `dir`, which is served at `url_path`.
+///
+/// Entries whose names start with a `.` are left out, and so are those for which `hidden`
+/// returns `true`. Directories come first, and both groups are sorted by name.
+pub fn listing<F>(dir: &Path, url_path: &str, mut hidden: F) -> io::Result<String>
+    where F: FnMut(&Path) -> bool
+{
+    let mut entries = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let name = match entry.file_name().into_string() {
+            Ok(name) => name,
+            // Names that aren't UTF-8 can't be linked to.
+            Err(_) => continue,
+        };
+        let path = entry.path();
+        if name.starts_with('.') || hidden(&path) {
+            continue;
+        }
+        // A link to a directory is listed as a directory, since that's where following it leads.
+        let is_dir = fs::metadata(&path).map(|metadata| metadata.is_dir()).unwrap_or(false);
+        entries.push((!is_dir, name));
+    }
+    entries.sort();
+
+    let title = format!("Index of {}", escape(url_path));
+    let mut html = format!("<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n\
+                            <title>{0}</title>\n</head>\n<body>\n<h1>{0}</h1>\n<ul>\n", title);
+    if url_path != "/" {
+        html.push_str("<li><a href=\"../\">../</a></li>\n");
+    }
+    for (is_file, name) in entries {
+        let slash = if is_file { "" } else { "/" };
+        let href = utf8_percent_encode(&name, PATH_SEGMENT_ENCODE_SET).to_string();
+        html.push_str(&format!("<li><a href=\"{}{}\">{}{}</a></li>\n",
+                               escape(&href), slash, escape(&name), slash));
+    }
+    html.push_str("</ul>\n</body>\n</html>\n");
+
+    Ok(html)
+}
+
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
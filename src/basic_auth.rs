@@ -0,0 +1,233 @@
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::sync::Arc;
+
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use iron::headers::{Authorization, Basic};
+use iron::prelude::*;
+use iron::status;
+use iron::typemap::Key;
+use md5::{Digest, Md5};
+use sha1::Sha1;
+
+/// The users of an Apache `htpasswd` file, which `BasicAuth` checks credentials against.
+///
+/// Passwords hashed with bcrypt (`htpasswd -B`), SHA-1 (`htpasswd -s`) or Apache's MD5
+/// (`htpasswd -m`, the default) are supported, as are MD5-crypt hashes starting with `$1$`.
+/// Lines that are empty or start with `#` are ignored.
+#[derive(Clone, Debug)]
+pub struct Htpasswd {
+    users: HashMap<String, String>,
+}
+
+impl Htpasswd {
+    /// Read the users from the file at `path`.
+    ///
+    /// Returns an error of kind `InvalidData` if a line isn't a user name and a supported hash
+    /// separated by a `:`.
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<Htpasswd> {
+        Htpasswd::parse(&fs::read_to_string(path)?)
+    }
+
+    /// Read the users from the contents of an `htpasswd` file.
+    pub fn parse(contents: &str) -> io::Result<Htpasswd> {
+        let mut users = HashMap::new();
+        for (number, line) in contents.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let invalid = |message: &str| {
+                io::Error::new(io::ErrorKind::InvalidData, format!("line {}: {}", number + 1, message))
+            };
+            let (user, hash) = match line.find(':') {
+                Some(colon) => (&line[..colon], &line[colon + 1..]),
+                None => return Err(invalid("expected user:hash")),
+            };
+            if Scheme::of(hash).is_none() {
+                return Err(invalid(&format!("unsupported password hash for {}", user)));
+            }
+            users.insert(user.to_owned(), hash.to_owned());
+        }
+        Ok(Htpasswd { users })
+    }
+
+    /// Returns `true` if `user` is in the file and `password` is theirs.
+    pub fn verify(&self, user: &str, password: &str) -> bool {
+        let hash = match self.users.get(user) {
+            Some(hash) => hash,
+            None => return false,
+        };
+        match Scheme::of(hash) {
+            Some(Scheme::Bcrypt) => bcrypt::verify(password, hash).unwrap_or(false),
+            Some(Scheme::Sha1) => {
+                let digest = STANDARD.encode(Sha1::digest(password.as_bytes()));
+                constant_time_eq(&hash.as_bytes()[5..], digest.as_bytes())
+            },
+            Some(Scheme::Md5(magic)) => {
+                let salt = hash[magic.len()..].split('$').next().unwrap_or("");
+                constant_time_eq(hash.as_bytes(), md5_crypt(password, salt, magic).as_bytes())
+            },
+            None => false,
+        }
+    }
+}
+
+enum Scheme {
+    Bcrypt,
+    Sha1,
+    /// MD5-crypt, with the magic string its hashes start with.
+    Md5(&'static str),
+}
+
+impl Scheme {
+    fn of(hash: &str) -> Option<Scheme> {
+        if ["$2a$", "$2b$", "$2y$"].iter().any(|prefix| hash.starts_with(prefix)) {
+            Some(Scheme::Bcrypt)
+        } else if hash.starts_with("{SHA}") {
+            Some(Scheme::Sha1)
+        } else if hash.starts_with("$apr1$") {
+            Some(Scheme::Md5("$apr1$"))
+        } else if hash.starts_with("$1$") {
+            Some(Scheme::Md5("$1$"))
+        } else {
+            None
+        }
+    }
+}
+
+/// Requires HTTP Basic authentication for files a `Static` protects with `basic_auth`.
+///
+/// Requests without the credentials of one of the users are answered with
+/// `401 Unauthorized` and a `WWW-Authenticate` challenge for the realm, and directory listings
+/// leave out what they couldn't open. Credentials are only sent in the clear over plain HTTP,
+/// so protected files should be served over HTTPS.
+///
+/// Passwords are checked on every request; bcrypt hashes with a high cost make each protected
+/// request correspondingly slower.
+///
+/// ## Example
+///
+/// ```ignore
+/// let staff = BasicAuth::new("Staff only", Htpasswd::open("/etc/staticfile/htpasswd")?);
+/// let site = Static::new("public").basic_auth("**/internal", staff);
+/// ```
+#[derive(Clone, Debug)]
+pub struct BasicAuth {
+    realm: String,
+    users: Arc<Htpasswd>,
+}
+
+impl BasicAuth {
+    /// Let `users` in, asking browsers for credentials for `realm`.
+    pub fn new(realm: &str, users: Htpasswd) -> BasicAuth {
+        BasicAuth { realm: realm.to_owned(), users: Arc::new(users) }
+    }
+
+    /// Returns `true` if `req` has the credentials of one of the users, checking them once per
+    /// request.
+    pub(crate) fn admits(&self, req: &mut Request) -> bool {
+        let id = &*self.users as *const Htpasswd as usize;
+        if let Some(&admitted) = req.extensions.get::<Verdicts>().and_then(|verdicts| verdicts.get(&id)) {
+            return admitted;
+        }
+        let admitted = match req.headers.get::<Authorization<Basic>>() {
+            Some(&Authorization(Basic { ref username, password: Some(ref password) })) => {
+                self.users.verify(username, password)
+            },
+            _ => false,
+        };
+        req.extensions.entry::<Verdicts>().or_insert_with(HashMap::new).insert(id, admitted);
+        admitted
+    }
+
+    /// The error answering a request without the credentials.
+    pub(crate) fn challenge(&self) -> IronError {
+        let mut error = IronError::new(Unauthorized, status::Unauthorized);
+        let realm = self.realm.replace('\\', "\\\\").replace('"', "\\\"");
+        let challenge = format!("Basic realm=\"{}\", charset=\"UTF-8\"", realm);
+        error.response.headers.set_raw("WWW-Authenticate", vec![challenge.into_bytes()]);
+        error
+    }
+}
+
+/// Whether a request's credentials were accepted, by the address of the users checked.
+struct Verdicts;
+
+impl Key for Verdicts {
+    type Value = HashMap<usize, bool>;
+}
+
+/// Thrown if a request for a protected file lacks the credentials for it. It is always
+/// accompanied by an Unauthorized response with a challenge.
+#[derive(Debug)]
+pub struct Unauthorized;
+
+impl Error for Unauthorized {
+    fn description(&self) -> &str { "Unauthorized" }
+}
+
+impl fmt::Display for Unauthorized {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("Unauthorized")
+    }
+}
+
+/// The MD5-crypt hash of `password`, as written by `htpasswd -m` when `magic` is `$apr1$`.
+fn md5_crypt(password: &str, salt: &str, magic: &str) -> String {
+    let password = password.as_bytes();
+    // Only the first 8 characters of the salt count.
+    let salt = &salt.as_bytes()[..salt.len().min(8)];
+
+    let alternate = Md5::new().chain_update(password).chain_update(salt).chain_update(password).finalize();
+    let mut context = Md5::new().chain_update(password).chain_update(magic).chain_update(salt);
+    for chunk in password.chunks(16) {
+        context.update(&alternate[..chunk.len()]);
+    }
+    let mut length = password.len();
+    while length > 0 {
+        if length & 1 == 1 {
+            context.update([0]);
+        } else {
+            context.update(&password[..1]);
+        }
+        length >>= 1;
+    }
+    let mut digest = context.finalize();
+
+    for round in 0..1000 {
+        let mut context = Md5::new();
+        if round & 1 == 1 { context.update(password) } else { context.update(digest) }
+        if round % 3 != 0 { context.update(salt) }
+        if round % 7 != 0 { context.update(password) }
+        if round & 1 == 1 { context.update(digest) } else { context.update(password) }
+        digest = context.finalize();
+    }
+
+    let mut hash = format!("{}{}$", magic, String::from_utf8_lossy(salt));
+    for &(a, b, c) in &[(0, 6, 12), (1, 7, 13), (2, 8, 14), (3, 9, 15), (4, 10, 5)] {
+        push_base64(&mut hash, (u32::from(digest[a]) << 16) | (u32::from(digest[b]) << 8) | u32::from(digest[c]), 4);
+    }
+    push_base64(&mut hash, u32::from(digest[11]), 2);
+    hash
+}
+
+/// Append the lowest `count` groups of 6 bits of `value`, lowest first, in crypt's alphabet.
+fn push_base64(hash: &mut String, mut value: u32, count: usize) {
+    const ALPHABET: &[u8] = b"./0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz";
+    for _ in 0..count {
+        hash.push(ALPHABET[(value & 0x3f) as usize] as char);
+        value >>= 6;
+    }
+}
+
+/// Compares without returning early, so the time taken doesn't tell how much of a guess was
+/// right.
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |difference, (x, y)| difference | (x ^ y)) == 0
+}
@@ -0,0 +1,163 @@
+extern crate getopts;
+extern crate iron;
+extern crate mount;
+extern crate staticfile;
+
+use std::env;
+use std::path::PathBuf;
+use std::process;
+use std::time::Duration;
+
+use getopts::{Matches, Options};
+use iron::prelude::*;
+use mount::Mount;
+use staticfile::{AllowedOrigin, Compression, ConfigReloader, Cors, LiveReload, Static};
+
+/// A directory to serve and the URL prefix to serve it at.
+struct Root {
+    prefix: String,
+    dir: PathBuf,
+}
+
+fn options() -> Options {
+    let mut opts = Options::new();
+    opts.optopt("b", "bind", "address to listen on (default 127.0.0.1)", "ADDR");
+    opts.optopt("p", "port", "port to listen on (default 3000)", "PORT");
+    opts.optopt("c", "cache", "let clients cache files for SECS seconds", "SECS");
+    opts.optmulti("i", "index", "serve NAME for directories, repeatable (default index.html)", "NAME");
+    opts.optflag("a", "autoindex", "list directories without an index file");
+    opts.optopt("s", "spa", "serve FILE for paths matching no file", "FILE");
+    opts.optmulti("", "cors", "allow requests from pages on ORIGIN, repeatable, or * for any", "ORIGIN");
+    opts.optflag("z", "compress", "gzip compressible files");
+    opts.optflag("l", "live-reload", "reload pages in the browser when files change, for development");
+    opts.optmulti("m", "mount", "serve DIR at PREFIX, repeatable", "/PREFIX=DIR");
+    opts.optopt("C", "config", "serve the mounts in a TOML or YAML FILE, reloading it on SIGHUP or when it changes", "FILE");
+    opts.optflag("h", "help", "print this help");
+    opts
+}
+
+fn roots(matches: &Matches) -> Result<Vec<Root>, String> {
+    let mut roots = Vec::new();
+
+    for mount in matches.opt_strs("mount") {
+        let mut parts = mount.splitn(2, '=');
+        match (parts.next(), parts.next()) {
+            (Some(prefix), Some(dir)) if prefix.starts_with('/') && !dir.is_empty() => {
+                roots.push(Root { prefix: prefix.to_owned(), dir: PathBuf::from(dir) });
+            },
+            _ => return Err(format!("invalid mount {:?}, expected /PREFIX=DIR", mount)),
+        }
+    }
+
+    match matches.free.len() {
+        0 if roots.is_empty() => roots.push(Root { prefix: "/".to_owned(), dir: PathBuf::from(".") }),
+        0 => {},
+        1 => roots.push(Root { prefix: "/".to_owned(), dir: PathBuf::from(&matches.free[0]) }),
+        _ => return Err("expected at most one directory".to_owned()),
+    }
+
+    for root in &roots {
+        if !root.dir.is_dir() {
+            return Err(format!("{} is not a directory", root.dir.display()));
+        }
+    }
+
+    Ok(roots)
+}
+
+fn handler(matches: &Matches, dir: PathBuf) -> Result<Static, String> {
+    let mut handler = Static::new(dir).autoindex(matches.opt_present("autoindex"));
+
+    if let Some(secs) = matches.opt_str("cache") {
+        let secs = secs.parse().map_err(|_| format!("invalid cache duration {:?}", secs))?;
+        handler = handler.cache(Duration::from_secs(secs));
+    }
+    let index = matches.opt_strs("index");
+    if !index.is_empty() {
+        handler = handler.index_files(&index);
+    }
+    if let Some(spa) = matches.opt_str("spa") {
+        handler = handler.spa_fallback(spa);
+    }
+    let origins = matches.opt_strs("cors");
+    if !origins.is_empty() {
+        let cors = origins.into_iter().fold(Cors::new(), |cors, origin| {
+            cors.allow_origin(if origin == "*" {
+                AllowedOrigin::Any
+            } else if origin.contains('*') {
+                AllowedOrigin::Wildcard(origin)
+            } else {
+                AllowedOrigin::Exact(origin)
+            })
+        });
+        handler = handler.set(cors);
+    }
+    if matches.opt_present("compress") {
+        handler = handler.set(Compression::new());
+    }
+    if matches.opt_present("live-reload") {
+        handler = handler.set(LiveReload::new());
+    }
+
+    Ok(handler)
+}
+
+fn run(args: &[String]) -> Result<(), String> {
+    let opts = options();
+    let matches = opts.parse(args).map_err(|e| e.to_string())?;
+
+    if matches.opt_present("help") {
+        let brief = "Usage: staticfile [options] [DIR]\n\n\
+                     Serve DIR, or the current directory if neither DIR nor a mount is given, over HTTP.";
+        print!("{}", opts.usage(brief));
+        return Ok(());
+    }
+
+    let bind = matches.opt_str("bind").unwrap_or_else(|| "127.0.0.1".to_owned());
+    let port: u16 = match matches.opt_str("port") {
+        Some(port) => port.parse().map_err(|_| format!("invalid port {:?}", port))?,
+        None => 3000,
+    };
+
+    if let Some(path) = matches.opt_str("config") {
+        if !matches.free.is_empty() || matches.opt_present("mount") {
+            return Err("--config can't be combined with DIR or --mount".to_owned());
+        }
+        // The config file sets these for each mount instead.
+        let ignored = ["cache", "index", "autoindex", "spa", "cors", "compress", "live-reload"];
+        if let Some(option) = ignored.iter().find(|&&option| matches.opt_present(option)) {
+            return Err(format!("--config can't be combined with --{}", option));
+        }
+        let config = ConfigReloader::with_error_handler(path, |path, e| {
+            eprintln!("staticfile: keeping the previous configuration, {} is invalid: {}",
+                      path.display(), e);
+        }).map_err(|e| e.to_string())?;
+        #[cfg(unix)]
+        let _sighup = config.reload_on_sighup().map_err(|e| e.to_string())?;
+        let _watch = config.watch(Duration::from_secs(2)).map_err(|e| e.to_string())?;
+
+        println!("Listening on http://{}:{}/", bind, port);
+        Iron::new(config.handler()).http((&bind[..], port)).map_err(|e| e.to_string())?;
+        return Ok(());
+    }
+
+    let mut mount = Mount::new();
+    for root in roots(&matches)? {
+        println!("Serving {} at {}", root.dir.display(), root.prefix);
+        mount.mount(&root.prefix, handler(&matches, root.dir)?);
+    }
+
+    println!("Listening on http://{}:{}/", bind, port);
+    Iron::new(mount).http((&bind[..], port)).map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+
+    if let Err(e) = run(&args) {
+        eprintln!("staticfile: {}", e);
+        eprintln!("Try `staticfile --help` for more information.");
+        process::exit(2);
+    }
+}
@@ -0,0 +1,176 @@
+use std::str::FromStr;
+use std::time::Duration;
+
+use iron::Headers;
+use iron::headers::{CacheControl, CacheDirective, Expires, HttpDate};
+use time;
+
+/// The `Cache-Control` directives sent for a set of files, given to `Cache::glob` and
+/// `Cache::mime`.
+///
+/// Directives are sent in the order they were added. With `expires` enabled, an `Expires`
+/// header is sent as well for HTTP/1.0 caches: `max-age` from now, or a date in the past if
+/// the policy has no `max-age`.
+///
+/// A policy can also be parsed from a `Cache-Control` value with `str::parse`.
+///
+/// ## Example
+///
+/// ```ignore
+/// let html = CachePolicy::new().no_cache();
+/// let shared = CachePolicy::new().public().max_age(Duration::from_secs(60))
+///     .s_maxage(Duration::from_secs(600))
+///     .stale_while_revalidate(Duration::from_secs(30));
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct CachePolicy {
+    directives: Vec<CacheDirective>,
+    expires: bool,
+}
+
+impl CachePolicy {
+    /// Create a policy without any directives.
+    pub fn new() -> CachePolicy {
+        CachePolicy::default()
+    }
+
+    /// Let shared caches store the response.
+    pub fn public(self) -> CachePolicy {
+        self.directive(CacheDirective::Public)
+    }
+
+    /// Keep the response out of shared caches.
+    pub fn private(self) -> CachePolicy {
+        self.directive(CacheDirective::Private)
+    }
+
+    /// Require caches to revalidate the response before every use.
+    pub fn no_cache(self) -> CachePolicy {
+        self.directive(CacheDirective::NoCache)
+    }
+
+    /// Forbid caches from storing the response at all.
+    pub fn no_store(self) -> CachePolicy {
+        self.directive(CacheDirective::NoStore)
+    }
+
+    /// Let the response be used without revalidation for `duration`.
+    pub fn max_age(self, duration: Duration) -> CachePolicy {
+        self.directive(CacheDirective::MaxAge(seconds(duration)))
+    }
+
+    /// Override `max_age` for shared caches.
+    pub fn s_maxage(self, duration: Duration) -> CachePolicy {
+        self.directive(CacheDirective::SMaxAge(seconds(duration)))
+    }
+
+    /// Let a stale response be used for `duration` while it is revalidated in the background.
+    pub fn stale_while_revalidate(self, duration: Duration) -> CachePolicy {
+        self.extension("stale-while-revalidate", Some(duration))
+    }
+
+    /// Let a stale response be used for `duration` if revalidating it fails.
+    pub fn stale_if_error(self, duration: Duration) -> CachePolicy {
+        self.extension("stale-if-error", Some(duration))
+    }
+
+    /// Forbid caches from using the response once it is stale without revalidating it.
+    pub fn must_revalidate(self) -> CachePolicy {
+        self.directive(CacheDirective::MustRevalidate)
+    }
+
+    /// Like `must_revalidate`, for shared caches only.
+    pub fn proxy_revalidate(self) -> CachePolicy {
+        self.directive(CacheDirective::ProxyRevalidate)
+    }
+
+    /// Forbid intermediaries from transforming the response, e.g. recompressing images.
+    pub fn no_transform(self) -> CachePolicy {
+        self.directive(CacheDirective::NoTransform)
+    }
+
+    /// Promise that the response will never change, so it is not revalidated while fresh.
+    pub fn immutable(self) -> CachePolicy {
+        self.extension("immutable", None)
+    }
+
+    /// Send an `Expires` header matching the policy.
+    pub fn expires(mut self, expires: bool) -> CachePolicy {
+        self.expires = expires;
+        self
+    }
+
+    /// Add the policy's headers to a response.
+    pub(crate) fn apply(&self, headers: &mut Headers) {
+        if !self.directives.is_empty() {
+            headers.set(CacheControl(self.directives.clone()));
+        }
+
+        if self.expires {
+            let max_age = self.directives.iter().filter_map(|directive| match *directive {
+                CacheDirective::MaxAge(seconds) => Some(seconds),
+                _ => None,
+            }).next();
+            let expires = match max_age {
+                Some(seconds) => time::now_utc() + time::Duration::seconds(i64::from(seconds)),
+                None => time::at_utc(time::Timespec::new(0, 0)),
+            };
+            headers.set(Expires(HttpDate(expires)));
+        }
+    }
+
+    fn directive(mut self, directive: CacheDirective) -> CachePolicy {
+        self.directives.push(directive);
+        self
+    }
+
+    fn extension(self, name: &str, duration: Option<Duration>) -> CachePolicy {
+        let value = duration.map(|duration| seconds(duration).to_string());
+        self.directive(CacheDirective::Extension(name.to_owned(), value))
+    }
+}
+
+impl FromStr for CachePolicy {
+    type Err = String;
+
+    /// Parse a `Cache-Control` value such as `"public, max-age=3600, immutable"`.
+    ///
+    /// Only the directives that `CachePolicy` has methods for are accepted.
+    fn from_str(value: &str) -> Result<CachePolicy, String> {
+        let mut policy = CachePolicy::new();
+
+        for directive in value.split(',').map(str::trim).filter(|directive| !directive.is_empty()) {
+            let mut parts = directive.splitn(2, '=');
+            let name = parts.next().unwrap().trim().to_lowercase();
+            let duration = match parts.next() {
+                Some(seconds) => match seconds.trim().trim_matches('"').parse() {
+                    Ok(seconds) => Some(Duration::from_secs(seconds)),
+                    Err(_) => return Err(format!("invalid number of seconds in `{}`", directive)),
+                },
+                None => None,
+            };
+
+            policy = match (&name[..], duration) {
+                ("public", None) => policy.public(),
+                ("private", None) => policy.private(),
+                ("no-cache", None) => policy.no_cache(),
+                ("no-store", None) => policy.no_store(),
+                ("must-revalidate", None) => policy.must_revalidate(),
+                ("proxy-revalidate", None) => policy.proxy_revalidate(),
+                ("no-transform", None) => policy.no_transform(),
+                ("immutable", None) => policy.immutable(),
+                ("max-age", Some(duration)) => policy.max_age(duration),
+                ("s-maxage", Some(duration)) => policy.s_maxage(duration),
+                ("stale-while-revalidate", Some(duration)) => policy.stale_while_revalidate(duration),
+                ("stale-if-error", Some(duration)) => policy.stale_if_error(duration),
+                _ => return Err(format!("unsupported Cache-Control directive `{}`", directive)),
+            };
+        }
+
+        Ok(policy)
+    }
+}
+
+fn seconds(duration: Duration) -> u32 {
+    duration.as_secs().min(u64::from(u32::MAX)) as u32
+}
@@ -0,0 +1,151 @@
+use std::io::{self, Write};
+
+use flate2;
+use flate2::write::GzEncoder;
+use iron::headers::{q, AcceptEncoding, ContentEncoding, ContentLength, Encoding};
+use iron::method::Method;
+use iron::mime::Mime;
+use iron::prelude::*;
+use iron::response::WriteBody;
+use iron::status;
+
+use cors::append_vary;
+use matcher::MimePattern;
+
+/// A modifier for `Static` to gzip files on the fly for clients that accept it.
+///
+/// Only files of a compressible type, by default text, scripts, JSON, XML, SVG and WebAssembly,
+/// that are at least `min_size` bytes long are compressed, and `Vary: Accept-Encoding` is
+/// added to their responses either way, including `304 Not Modified`. Compressed responses are
+/// sent without `Content-Length`, and keep their weak `ETag` since they are equivalent to the
+/// original.
+///
+/// ## Example
+///
+/// ```ignore
+/// let compression = Compression::new().level(9).mime("application/x-ndjson");
+/// let static_handler = Static::new(path).set(compression);
+/// ```
+#[derive(Clone, Debug)]
+pub struct Compression {
+    types: Vec<MimePattern>,
+    min_size: u64,
+    level: u32,
+}
+
+impl Compression {
+    /// Compress the default types at level 6 if they are at least 1 KiB.
+    pub fn new() -> Compression {
+        Compression {
+            types: COMPRESSIBLE.iter().map(|pattern| MimePattern::expect(pattern)).collect(),
+            min_size: 1024,
+            level: 6,
+        }
+    }
+
+    /// Also compress files served with a type matching `pattern`, such as `font/*`.
+    ///
+    /// ## Panics
+    ///
+    /// Panics if `pattern` is not of the form `type/subtype`.
+    pub fn mime(mut self, pattern: &str) -> Compression {
+        self.types.push(MimePattern::expect(pattern));
+        self
+    }
+
+    /// Leave files smaller than `min_size` bytes uncompressed, since the gain can't outweigh
+    /// the overhead.
+    pub fn min_size(mut self, min_size: u64) -> Compression {
+        self.min_size = min_size;
+        self
+    }
+
+    /// Set the gzip level from 0, no compression, to 9, the smallest output.
+    pub fn level(mut self, level: u32) -> Compression {
+        self.level = level.min(9);
+        self
+    }
+
+    /// Compress the body of a response for a file served as `mime`, if it is worth it and
+    /// `req` accepts gzip. Returns whether the response was compressed.
+    pub(crate) fn apply(&self, req: &Request, mime: &Mime, response: &mut Response) -> bool {
+        let revalidated = match response.status {
+            Some(status::Ok) => false,
+            Some(status::NotModified) => true,
+            _ => return false,
+        };
+        if !self.types.iter().any(|pattern| pattern.matches(mime)) {
+            return false;
+        }
+        // A 304 repeats the `Vary` of the response it stands for, but has no body to compress.
+        append_vary(&mut response.headers, "Accept-Encoding");
+        if revalidated {
+            return false;
+        }
+
+        let large_enough = response.headers.get::<ContentLength>()
+            .is_none_or(|&ContentLength(len)| len >= self.min_size);
+        if !large_enough || !accepts_gzip(req) {
+            return false;
+        }
+
+        response.headers.remove::<ContentLength>();
+        response.headers.set(ContentEncoding(vec![Encoding::Gzip]));
+        // A HEAD response has no body to compress, but gets the same headers.
+        if req.method != Method::Head {
+            if let Some(body) = response.body.take() {
+                response.body = Some(Box::new(GzipBody { body, level: self.level }));
+            }
+        }
+
+        true
+    }
+}
+
+impl Default for Compression {
+    fn default() -> Compression {
+        Compression::new()
+    }
+}
+
+fn accepts_gzip(req: &Request) -> bool {
+    match req.headers.get::<AcceptEncoding>() {
+        Some(AcceptEncoding(encodings)) => encodings.iter().any(|quality| {
+            quality.item == Encoding::Gzip && quality.quality > q(0.0)
+        }),
+        None => false,
+    }
+}
+
+struct GzipBody {
+    body: Box<dyn WriteBody>,
+    level: u32,
+}
+
+impl WriteBody for GzipBody {
+    fn write_body(&mut self, res: &mut dyn Write) -> io::Result<()> {
+        let mut encoder = GzEncoder::new(res, flate2::Compression::new(self.level));
+        self.body.write_body(&mut encoder)?;
+        encoder.finish().map(|_| ())
+    }
+}
+
+const COMPRESSIBLE: &[&str] = &[
+    "text/*",
+    "application/javascript",
+    "application/json",
+    "application/ld+json",
+    "application/manifest+json",
+    "application/xml",
+    "application/xhtml+xml",
+    "application/rss+xml",
+    "application/atom+xml",
+    "application/wasm",
+    "application/yaml",
+    "application/toml",
+    "image/svg+xml",
+    "image/bmp",
+    "image/vnd.microsoft.icon",
+    "font/ttf",
+    "font/otf",
+];
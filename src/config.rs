@@ -0,0 +1,467 @@
+use std::convert::TryFrom;
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::marker::PhantomData;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use iron::{status, Set};
+use mount::Mount;
+use serde::de::{self, Deserialize, Deserializer, Unexpected, Visitor};
+use serde_yaml;
+use toml;
+
+use cache_policy::CachePolicy;
+use matcher::MimePattern;
+use path_pattern::PathPattern;
+use security_headers::SecurityHeaders;
+use static_handler::{Cache, Static};
+
+/// A set of `Static` handlers and the prefixes they are mounted at, read from TOML or YAML.
+///
+/// Each `mount` has a `root` directory, relative to the current directory, and may set a
+/// `prefix` (`/` by default), `index` files, `autoindex`, a `spa` fallback page, a `cache`
+/// policy, extra `headers`, `redirects`, and globs of files to `deny`. Unknown keys are
+/// rejected, and so are invalid values such as a malformed glob or a root that isn't a
+/// directory, with an error naming the key and its line.
+///
+/// ## Example
+///
+/// ```toml
+/// [[mount]]
+/// prefix = "/"
+/// root = "public"
+/// deny = ["**/.git/**", "**/*.map"]
+///
+/// [mount.cache]
+/// max_age = 3600
+/// fingerprinted = true
+/// rules = [{ mime = "text/html", cache_control = "no-cache" }]
+///
+/// [[mount.headers]]
+/// name = "X-Frame-Options"
+/// value = "DENY"
+///
+/// [[mount.redirects]]
+/// from = "/old.html"
+/// to = "/new.html"
+/// status = 308
+/// ```
+///
+/// ```ignore
+/// let config = Config::from_file("staticfile.toml")?;
+/// Iron::new(config.mount()).http("localhost:3000")?;
+/// ```
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    #[serde(rename = "mount")]
+    mounts: Vec<MountConfig>,
+}
+
+impl Config {
+    /// Parse and validate a TOML configuration.
+    pub fn from_toml(source: &str) -> Result<Config, ConfigError> {
+        toml::from_str(source).map_err(ConfigError::Toml)
+    }
+
+    /// Parse and validate a YAML configuration.
+    pub fn from_yaml(source: &str) -> Result<Config, ConfigError> {
+        serde_yaml::from_str(source).map_err(ConfigError::Yaml)
+    }
+
+    /// Read a configuration from a `.toml`, `.yaml` or `.yml` file.
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Config, ConfigError> {
+        let path = path.as_ref();
+        let extension = path.extension().and_then(|extension| extension.to_str());
+        let parse: fn(&str) -> Result<Config, ConfigError> = match extension {
+            Some("toml") => Config::from_toml,
+            Some("yaml") | Some("yml") => Config::from_yaml,
+            _ => return Err(ConfigError::UnknownFormat(path.to_owned())),
+        };
+        parse(&fs::read_to_string(path).map_err(ConfigError::Io)?)
+    }
+
+    /// Build a `Static` handler for each mount, with the prefix to mount it at.
+    pub fn handlers(&self) -> Vec<(String, Static)> {
+        self.mounts.iter().map(|mount| (mount.prefix.0.clone(), mount.handler())).collect()
+    }
+
+    /// Build a `Mount` of the configured `Static` handlers.
+    pub fn mount(&self) -> Mount {
+        let mut mount = Mount::new();
+        for (prefix, handler) in self.handlers() {
+            mount.mount(&prefix, handler);
+        }
+        mount
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct MountConfig {
+    #[serde(default)]
+    prefix: Prefix,
+    root: Root,
+    index: Option<Vec<String>>,
+    #[serde(default)]
+    autoindex: bool,
+    spa: Option<PathBuf>,
+    cache: Option<CacheConfig>,
+    #[serde(default)]
+    headers: Vec<HeaderConfig>,
+    #[serde(default)]
+    redirects: Vec<RedirectConfig>,
+    #[serde(default)]
+    deny: Vec<Glob>,
+}
+
+impl MountConfig {
+    fn handler(&self) -> Static {
+        let mut handler = Static::new(&self.root.0).autoindex(self.autoindex);
+        if let Some(ref index) = self.index {
+            handler = handler.index_files(index);
+        }
+        if let Some(ref spa) = self.spa {
+            handler = handler.spa_fallback(spa);
+        }
+        if let Some(ref cache) = self.cache {
+            handler = handler.set(cache.cache());
+        }
+        if !self.headers.is_empty() {
+            let headers = self.headers.iter().fold(SecurityHeaders::new(), |headers, header| {
+                let value = header.value.as_deref();
+                match (&header.selector, value) {
+                    (Some(Selector::Glob(pattern)), _) => headers.glob(pattern, &header.name, value),
+                    (Some(Selector::Mime(pattern)), _) => headers.mime(pattern, &header.name, value),
+                    (None, Some(value)) => headers.header(&header.name, value),
+                    (None, None) => headers,
+                }
+            });
+            handler = handler.set(headers);
+        }
+        for redirect in &self.redirects {
+            handler = handler.redirect(&redirect.from, &redirect.to, redirect.status.0);
+        }
+        for pattern in &self.deny {
+            handler = handler.deny(&pattern.0);
+        }
+        handler
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct CacheConfig {
+    max_age: u64,
+    #[serde(default)]
+    fingerprinted: bool,
+    #[serde(default)]
+    rules: Vec<CacheRule>,
+}
+
+impl CacheConfig {
+    fn cache(&self) -> Cache {
+        let mut cache = Cache::new(Duration::from_secs(self.max_age));
+        if self.fingerprinted {
+            cache = cache.fingerprinted();
+        }
+        for rule in &self.rules {
+            cache = match rule.selector {
+                Selector::Glob(ref pattern) => cache.glob(pattern, rule.policy.clone()),
+                Selector::Mime(ref pattern) => cache.mime(pattern, rule.policy.clone()),
+            };
+        }
+        cache
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(try_from = "RawCacheRule")]
+struct CacheRule {
+    selector: Selector,
+    policy: CachePolicy,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawCacheRule {
+    glob: Option<Glob>,
+    mime: Option<Mime>,
+    cache_control: Policy,
+    #[serde(default)]
+    expires: bool,
+}
+
+impl TryFrom<RawCacheRule> for CacheRule {
+    type Error = String;
+
+    fn try_from(raw: RawCacheRule) -> Result<CacheRule, String> {
+        match Selector::new(raw.glob, raw.mime)? {
+            Some(selector) => Ok(CacheRule { selector, policy: raw.cache_control.0.expires(raw.expires) }),
+            None => Err("a cache rule needs a `glob` or a `mime`".to_owned()),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(try_from = "RawHeaderConfig")]
+struct HeaderConfig {
+    name: String,
+    value: Option<String>,
+    selector: Option<Selector>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawHeaderConfig {
+    name: String,
+    value: Option<String>,
+    glob: Option<Glob>,
+    mime: Option<Mime>,
+}
+
+impl TryFrom<RawHeaderConfig> for HeaderConfig {
+    type Error = String;
+
+    fn try_from(raw: RawHeaderConfig) -> Result<HeaderConfig, String> {
+        let is_token = |c: char| c.is_ascii_alphanumeric() || "!#$%&'*+-.^_`|~".contains(c);
+        if raw.name.is_empty() || !raw.name.chars().all(is_token) {
+            return Err(format!("invalid header name {:?}", raw.name));
+        }
+        let selector = Selector::new(raw.glob, raw.mime)?;
+        // Without a selector, a header is sent with every file, so there is nothing to remove.
+        if selector.is_none() && raw.value.is_none() {
+            return Err(format!("header {:?} needs a `value` unless it has a `glob` or a `mime`", raw.name));
+        }
+        Ok(HeaderConfig { name: raw.name, value: raw.value, selector })
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RedirectConfig {
+    from: String,
+    to: String,
+    #[serde(default)]
+    status: RedirectStatus,
+}
+
+#[derive(Clone, Debug)]
+enum Selector {
+    Glob(String),
+    Mime(String),
+}
+
+impl Selector {
+    fn new(glob: Option<Glob>, mime: Option<Mime>) -> Result<Option<Selector>, String> {
+        match (glob, mime) {
+            (Some(_), Some(_)) => Err("`glob` and `mime` can't both be set".to_owned()),
+            (Some(glob), None) => Ok(Some(Selector::Glob(glob.0))),
+            (None, Some(mime)) => Ok(Some(Selector::Mime(mime.0))),
+            (None, None) => Ok(None),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+struct Prefix(String);
+
+impl Default for Prefix {
+    fn default() -> Prefix {
+        Prefix("/".to_owned())
+    }
+}
+
+impl TryFrom<String> for Prefix {
+    type Error = String;
+
+    fn try_from(prefix: String) -> Result<Prefix, String> {
+        if prefix.starts_with('/') {
+            Ok(Prefix(prefix))
+        } else {
+            Err(format!("prefix {:?} doesn't start with `/`", prefix))
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+struct Root(PathBuf);
+
+impl TryFrom<String> for Root {
+    type Error = String;
+
+    fn try_from(root: String) -> Result<Root, String> {
+        if Path::new(&root).is_dir() {
+            Ok(Root(root.into()))
+        } else {
+            Err(format!("root {:?} is not a directory", root))
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+struct Glob(String);
+
+impl TryFrom<String> for Glob {
+    type Error = String;
+
+    fn try_from(pattern: String) -> Result<Glob, String> {
+        match PathPattern::new(&pattern) {
+            Ok(_) => Ok(Glob(pattern)),
+            Err(e) => Err(format!("invalid glob {:?}: {}", pattern, e.msg)),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+struct Mime(String);
+
+impl TryFrom<String> for Mime {
+    type Error = String;
+
+    fn try_from(pattern: String) -> Result<Mime, String> {
+        match MimePattern::new(&pattern) {
+            Some(_) => Ok(Mime(pattern)),
+            None => Err(format!("invalid MIME pattern {:?}, expected `type/subtype`", pattern)),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+struct Policy(CachePolicy);
+
+impl TryFrom<String> for Policy {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Policy, String> {
+        value.parse().map(Policy)
+    }
+}
+
+#[derive(Clone, Debug)]
+struct RedirectStatus(status::Status);
+
+impl Default for RedirectStatus {
+    fn default() -> RedirectStatus {
+        RedirectStatus(status::MovedPermanently)
+    }
+}
+
+impl TryFrom<u64> for RedirectStatus {
+    type Error = String;
+
+    fn try_from(code: u64) -> Result<RedirectStatus, String> {
+        match code {
+            301 | 302 | 303 | 307 | 308 => Ok(RedirectStatus(status::Status::from_u16(code as u16))),
+            _ => Err(format!("invalid redirect status {}, expected 301, 302, 303, 307 or 308", code)),
+        }
+    }
+}
+
+/// Validates strings with `T::try_from` while the deserializer still knows where they are, so
+/// that errors name the key and line of the value rather than those of its table.
+struct Validated<T>(PhantomData<T>);
+
+impl<'de, T: TryFrom<String, Error = String>> Visitor<'de> for Validated<T> {
+    type Value = T;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a string")
+    }
+
+    fn visit_str<E: de::Error>(self, value: &str) -> Result<T, E> {
+        T::try_from(value.to_owned()).map_err(E::custom)
+    }
+}
+
+macro_rules! deserialize_validated {
+    ($($ty:ident),*) => {$(
+        impl<'de> Deserialize<'de> for $ty {
+            fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<$ty, D::Error> {
+                deserializer.deserialize_str(Validated(PhantomData))
+            }
+        }
+    )*}
+}
+
+deserialize_validated!(Prefix, Root, Glob, Mime, Policy);
+
+impl<'de> Deserialize<'de> for RedirectStatus {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<RedirectStatus, D::Error> {
+        struct Code;
+
+        impl<'de> Visitor<'de> for Code {
+            type Value = RedirectStatus;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("an HTTP status code")
+            }
+
+            fn visit_u64<E: de::Error>(self, code: u64) -> Result<RedirectStatus, E> {
+                RedirectStatus::try_from(code).map_err(E::custom)
+            }
+
+            fn visit_i64<E: de::Error>(self, code: i64) -> Result<RedirectStatus, E> {
+                match u64::try_from(code) {
+                    Ok(code) => self.visit_u64(code),
+                    Err(_) => Err(E::invalid_value(Unexpected::Signed(code), &self)),
+                }
+            }
+        }
+
+        deserializer.deserialize_u16(Code)
+    }
+}
+
+/// An error reading a `Config`.
+#[derive(Debug)]
+pub enum ConfigError {
+    /// The file couldn't be read.
+    Io(io::Error),
+    /// The TOML is malformed or doesn't match the schema.
+    Toml(toml::de::Error),
+    /// The YAML is malformed or doesn't match the schema.
+    Yaml(serde_yaml::Error),
+    /// The file's extension is not `.toml`, `.yaml` or `.yml`.
+    UnknownFormat(PathBuf),
+}
+
+impl ConfigError {
+    /// The line of the configuration, starting from 1, that the error was found on, if known.
+    pub fn line(&self) -> Option<usize> {
+        match *self {
+            ConfigError::Toml(ref e) => e.line_col().map(|(line, _)| line + 1),
+            ConfigError::Yaml(ref e) => e.location().map(|location| location.line()),
+            ConfigError::Io(_) | ConfigError::UnknownFormat(_) => None,
+        }
+    }
+}
+
+impl Error for ConfigError {
+    fn description(&self) -> &str { "Invalid configuration" }
+
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match *self {
+            ConfigError::Io(ref e) => Some(e),
+            ConfigError::Toml(ref e) => Some(e),
+            ConfigError::Yaml(ref e) => Some(e),
+            ConfigError::UnknownFormat(_) => None,
+        }
+    }
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ConfigError::Io(ref e) => write!(f, "{}", e),
+            ConfigError::Toml(ref e) => write!(f, "{}", e),
+            ConfigError::Yaml(ref e) => write!(f, "{}", e),
+            ConfigError::UnknownFormat(ref path) => {
+                write!(f, "{}: expected a .toml, .yaml or .yml file", path.display())
+            },
+        }
+    }
+}
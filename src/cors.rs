@@ -0,0 +1,218 @@
+use std::time::Duration;
+
+use iron::prelude::*;
+use iron::status;
+use iron::Headers;
+use regex::Regex;
+
+/// An origin that `Cors` lets read responses.
+#[derive(Clone, Debug)]
+pub enum AllowedOrigin {
+    /// Any origin at all.
+    Any,
+    /// A single origin, such as `https://app.example.com`.
+    Exact(String),
+    /// Origins matching a pattern in which `*` stands for any run of characters, such as
+    /// `https://*.example.com`.
+    Wildcard(String),
+    /// Origins matching a regular expression, which should be anchored.
+    Regex(Regex),
+}
+
+impl AllowedOrigin {
+    fn matches(&self, origin: &str) -> bool {
+        match *self {
+            AllowedOrigin::Any => true,
+            AllowedOrigin::Exact(ref allowed) => allowed == origin,
+            AllowedOrigin::Wildcard(ref pattern) => wildcard_match(pattern, origin),
+            AllowedOrigin::Regex(ref regex) => regex.is_match(origin),
+        }
+    }
+}
+
+/// A modifier for `Static` to let pages on other origins read the served files.
+///
+/// Requests carrying an allowed `Origin` get `Access-Control-Allow-Origin` and the other
+/// configured CORS headers, and CORS preflight `OPTIONS` requests are answered directly. Unless
+/// any origin is allowed without credentials, in which case the response is the same for
+/// everyone and `*` is sent, the request's origin is reflected and `Vary: Origin` is added so
+/// that shared caches keep the responses for different origins apart.
+///
+/// Credentials are only allowed for origins listed by name or pattern. An origin that is only
+/// allowed by `AllowedOrigin::Any` gets `*` and no `Access-Control-Allow-Credentials`, so that
+/// no site can read files protected by cookies or authentication on behalf of their users.
+///
+/// ## Example
+///
+/// ```ignore
+/// let cors = Cors::new()
+///     .allow_origin(AllowedOrigin::Wildcard("https://*.example.com".to_owned()))
+///     .expose_header("ETag")
+///     .max_age(Duration::from_secs(3600));
+/// let static_handler = Static::new(path).set(cors);
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct Cors {
+    origins: Vec<AllowedOrigin>,
+    expose_headers: Vec<String>,
+    allow_headers: Vec<String>,
+    credentials: bool,
+    max_age: Option<Duration>,
+}
+
+impl Cors {
+    /// Create a policy that allows no origins yet.
+    pub fn new() -> Cors {
+        Cors::default()
+    }
+
+    /// Allow requests from `origin`.
+    pub fn allow_origin(mut self, origin: AllowedOrigin) -> Cors {
+        self.origins.push(origin);
+        self
+    }
+
+    /// Let scripts read a response header they couldn't otherwise, e.g. `Content-Range`.
+    pub fn expose_header(mut self, header: &str) -> Cors {
+        self.expose_headers.push(header.to_owned());
+        self
+    }
+
+    /// Let preflighted requests carry a request header that isn't CORS-safelisted.
+    pub fn allow_header(mut self, header: &str) -> Cors {
+        self.allow_headers.push(header.to_owned());
+        self
+    }
+
+    /// Let requests include credentials such as cookies, from origins allowed by name or
+    /// pattern; never from origins only allowed by `AllowedOrigin::Any`.
+    pub fn credentials(mut self, credentials: bool) -> Cors {
+        self.credentials = credentials;
+        self
+    }
+
+    /// Let browsers cache the answer to a preflight request for `max_age`.
+    pub fn max_age(mut self, max_age: Duration) -> Cors {
+        self.max_age = Some(max_age);
+        self
+    }
+
+    /// Answer a CORS preflight request, or return `None` if `req` isn't one.
+    ///
+    /// Preflights from origins that aren't allowed get a response without CORS headers,
+    /// which the browser treats as a refusal.
+    pub(crate) fn preflight(&self, req: &Request, allowed_methods: &str) -> Option<Response> {
+        let requested_method = header_str(&req.headers, "Access-Control-Request-Method")?;
+        let mut response = Response::with(status::Ok);
+
+        let (origin, any_only) = match self.allowed_origin(&req.headers) {
+            Some(allowed) => allowed,
+            None => return Some(response),
+        };
+        if !allowed_methods.split(", ").any(|method| method == requested_method) {
+            return Some(response);
+        }
+
+        self.set_origin(&mut response.headers, origin, any_only);
+        response.headers.set_raw("Access-Control-Allow-Methods",
+                                 vec![allowed_methods.as_bytes().to_vec()]);
+        if !self.allow_headers.is_empty() {
+            response.headers.set_raw("Access-Control-Allow-Headers",
+                                     vec![self.allow_headers.join(", ").into_bytes()]);
+        }
+        if let Some(max_age) = self.max_age {
+            response.headers.set_raw("Access-Control-Max-Age",
+                                     vec![max_age.as_secs().to_string().into_bytes()]);
+        }
+
+        Some(response)
+    }
+
+    /// Add the CORS headers for `req` to a response.
+    pub(crate) fn apply(&self, req: &Request, headers: &mut Headers) {
+        if let Some((origin, any_only)) = self.allowed_origin(&req.headers) {
+            self.set_origin(headers, origin, any_only);
+            if !self.expose_headers.is_empty() {
+                headers.set_raw("Access-Control-Expose-Headers",
+                                vec![self.expose_headers.join(", ").into_bytes()]);
+            }
+        } else if self.reflects_origin() {
+            append_vary(headers, "Origin");
+        }
+    }
+
+    /// The request's origin if it is allowed, and whether it is only allowed by `Any`.
+    fn allowed_origin<'a>(&self, headers: &'a Headers) -> Option<(&'a str, bool)> {
+        let origin = header_str(headers, "Origin")?;
+        let mut matching = self.origins.iter().filter(|allowed| allowed.matches(origin)).peekable();
+        matching.peek()?;
+        let any_only = matching.all(|allowed| matches!(*allowed, AllowedOrigin::Any));
+        Some((origin, any_only))
+    }
+
+    fn reflects_origin(&self) -> bool {
+        self.credentials || !self.origins.iter().any(|allowed| matches!(*allowed, AllowedOrigin::Any))
+    }
+
+    fn set_origin(&self, headers: &mut Headers, origin: &str, any_only: bool) {
+        if !self.reflects_origin() {
+            headers.set_raw("Access-Control-Allow-Origin", vec![b"*".to_vec()]);
+        } else if any_only {
+            // Browsers refuse credentialed responses with `*`.
+            headers.set_raw("Access-Control-Allow-Origin", vec![b"*".to_vec()]);
+            append_vary(headers, "Origin");
+        } else {
+            headers.set_raw("Access-Control-Allow-Origin", vec![origin.as_bytes().to_vec()]);
+            append_vary(headers, "Origin");
+            if self.credentials {
+                headers.set_raw("Access-Control-Allow-Credentials", vec![b"true".to_vec()]);
+            }
+        }
+    }
+}
+
+fn header_str<'a>(headers: &'a Headers, name: &str) -> Option<&'a str> {
+    headers.get_raw(name)
+        .and_then(|values| values.first())
+        .and_then(|value| ::std::str::from_utf8(value).ok())
+}
+
+/// Add `name` to the `Vary` header unless it is already listed.
+pub(crate) fn append_vary(headers: &mut Headers, name: &str) {
+    let listed = headers.get_raw("Vary").is_some_and(|values| {
+        values.iter()
+            .filter_map(|value| ::std::str::from_utf8(value).ok())
+            .flat_map(|value| value.split(','))
+            .any(|item| item.trim().eq_ignore_ascii_case(name) || item.trim() == "*")
+    });
+
+    if !listed {
+        headers.append_raw("Vary", name.as_bytes().to_vec());
+    }
+}
+
+/// Match `text` against `pattern`, where `*` stands for any run of characters.
+fn wildcard_match(pattern: &str, text: &str) -> bool {
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or("");
+    if !text.starts_with(first) {
+        return false;
+    }
+
+    let mut rest = &text[first.len()..];
+    let mut parts: Vec<&str> = parts.collect();
+    let last = match parts.pop() {
+        Some(last) => last,
+        // No `*` at all.
+        None => return rest.is_empty(),
+    };
+
+    for part in parts {
+        match rest.find(part) {
+            Some(index) => rest = &rest[index + part.len()..],
+            None => return false,
+        }
+    }
+
+    rest.len() >= last.len() && rest.ends_with(last)
+}
@@ -0,0 +1,55 @@
+use std::fs::File;
+use std::io::{self, Read, Write};
+
+use iron::response::WriteBody;
+
+/// Size of the userspace buffer the file is copied through.
+const BUFFER_SIZE: usize = 64 * 1024;
+
+/// The response body of a file served by `Static`.
+///
+/// Iron hands bodies an opaque, buffered `Write` without a file descriptor, so the file is
+/// always copied through a userspace buffer; `sendfile(2)` can't be used with Iron 0.5 or 0.6.
+pub struct FileBody {
+    file: File,
+}
+
+impl FileBody {
+    /// Create a body that writes `file` from its current offset to the end.
+    pub fn new(file: File) -> FileBody {
+        FileBody { file }
+    }
+}
+
+impl WriteBody for FileBody {
+    fn write_body(&mut self, res: &mut dyn Write) -> io::Result<()> {
+        buffered_copy(&mut self.file, res).map(|_| ())
+    }
+}
+
+/// The body of a `HEAD` response, which keeps the `Content-Length` of the file but sends
+/// nothing.
+pub struct EmptyBody;
+
+impl WriteBody for EmptyBody {
+    fn write_body(&mut self, _: &mut dyn Write) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+fn buffered_copy<W: Write + ?Sized>(file: &mut File, out: &mut W) -> io::Result<u64> {
+    let mut buffer = vec![0; BUFFER_SIZE];
+    let mut written = 0;
+
+    loop {
+        let read = match file.read(&mut buffer) {
+            Ok(0) => return Ok(written),
+            Ok(read) => read,
+            Err(ref e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        };
+
+        out.write_all(&buffer[..read])?;
+        written += read as u64;
+    }
+}
@@ -0,0 +1,40 @@
+use std::collections::HashSet;
+use std::fs::{self, Metadata};
+use std::path::{Path, PathBuf};
+
+/// The device and inode of a file, which change when another file is renamed over it.
+#[cfg(unix)]
+pub fn file_id(metadata: &Metadata) -> Option<(u64, u64)> {
+    use std::os::unix::fs::MetadataExt;
+    Some((metadata.dev(), metadata.ino()))
+}
+
+#[cfg(not(unix))]
+pub fn file_id(_: &Metadata) -> Option<(u64, u64)> {
+    None
+}
+
+/// The directories a walk below the root has entered, so that symlinks pointing back up the
+/// tree don't send it round in circles.
+///
+/// A directory reachable by several paths is only entered by the first one found.
+#[derive(Default)]
+pub struct Visited {
+    ids: HashSet<(u64, u64)>,
+    /// Directories known by their canonical path, where there are no inode numbers.
+    paths: HashSet<PathBuf>,
+}
+
+impl Visited {
+    pub fn new() -> Visited {
+        Visited::default()
+    }
+
+    /// Returns `true` the first time it is called for the directory at `path`.
+    pub fn enter(&mut self, path: &Path, metadata: &Metadata) -> bool {
+        match file_id(metadata) {
+            Some(id) => self.ids.insert(id),
+            None => self.paths.insert(fs::canonicalize(path).unwrap_or_else(|_| path.to_owned())),
+        }
+    }
+}
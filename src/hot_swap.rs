@@ -0,0 +1,61 @@
+use std::sync::{Arc, RwLock};
+
+use iron::prelude::*;
+use iron::Handler;
+
+/// A `Handler` whose inner handler, such as a `Static` or a `Mount` of them, can be replaced
+/// while the server is running.
+///
+/// Clones share the same inner handler, so one clone can be given to Iron and another kept to
+/// `swap` in a handler with a new root or cache policy. Each request is served by the handler
+/// that was current when it arrived, even if it is swapped out before the response is done.
+///
+/// ## Example
+///
+/// ```ignore
+/// let site = HotSwap::new(Static::new("public"));
+/// let server = Iron::new(site.clone()).http("localhost:3000")?;
+/// // Later, from another thread:
+/// site.swap(Static::new("public-v2").cache(Duration::from_secs(3600)));
+/// ```
+pub struct HotSwap<H> {
+    current: Arc<RwLock<Arc<H>>>,
+}
+
+impl<H: Handler> HotSwap<H> {
+    /// Serve requests with `handler` until it is swapped.
+    pub fn new(handler: H) -> HotSwap<H> {
+        HotSwap { current: Arc::new(RwLock::new(Arc::new(handler))) }
+    }
+
+    /// The handler new requests are served with.
+    pub fn current(&self) -> Arc<H> {
+        match self.current.read() {
+            Ok(current) => current.clone(),
+            // Swapping can't leave the handler half-replaced, so a panic elsewhere doesn't matter.
+            Err(poisoned) => poisoned.into_inner().clone(),
+        }
+    }
+
+    /// Serve new requests with `handler`, returning the handler it replaces.
+    pub fn swap(&self, handler: H) -> Arc<H> {
+        let handler = Arc::new(handler);
+        match self.current.write() {
+            Ok(mut current) => ::std::mem::replace(&mut *current, handler),
+            Err(poisoned) => ::std::mem::replace(&mut *poisoned.into_inner(), handler),
+        }
+    }
+}
+
+impl<H> Clone for HotSwap<H> {
+    fn clone(&self) -> HotSwap<H> {
+        HotSwap { current: self.current.clone() }
+    }
+}
+
+impl<H: Handler> Handler for HotSwap<H> {
+    fn handle(&self, req: &mut Request) -> IronResult<Response> {
+        // The lock is only held to clone the `Arc`, never while a request is being served.
+        self.current().handle(req)
+    }
+}
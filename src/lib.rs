@@ -6,16 +6,103 @@
 
 extern crate time;
 
-#[cfg(feature = "cache")]
-extern crate filetime;
+#[cfg(all(feature = "watch", target_os = "linux"))]
+extern crate libc;
+
+#[cfg(all(feature = "watch", target_os = "linux"))]
+extern crate inotify;
+
+#[cfg(feature = "mmap")]
+extern crate memmap;
+
+#[cfg(feature = "gzip")]
+extern crate flate2;
+
+#[cfg(feature = "config")]
+extern crate serde;
+#[cfg(feature = "config")]
+#[macro_use]
+extern crate serde_derive;
+#[cfg(feature = "config")]
+extern crate serde_yaml;
+#[cfg(feature = "config")]
+extern crate toml;
 
+#[cfg(all(feature = "reload", unix))]
+extern crate signal_hook;
+
+#[cfg(feature = "auth")]
+extern crate base64;
+#[cfg(feature = "auth")]
+extern crate bcrypt;
+#[cfg(feature = "auth")]
+extern crate md5;
+#[cfg(feature = "auth")]
+extern crate sha1;
+
+extern crate glob;
 extern crate iron;
 extern crate mount;
+extern crate regex;
 extern crate url;
 
+pub use access_log::{AccessLog, AccessLogEntry, LogFormat, LogWriter};
+pub use authorizer::{Access, Authorizer};
+#[cfg(feature = "gzip")]
+pub use compression::Compression;
+#[cfg(feature = "config")]
+pub use config::{Config, ConfigError};
+pub use cors::{AllowedOrigin, Cors};
+pub use hot_swap::HotSwap;
+pub use live_reload::LiveReload;
+pub use manifest::{AssetManifest, OutdatedAssets};
+pub use metrics::{Metrics, MetricsRegistry};
+pub use mime_types::MimeTypes;
+pub use security_headers::SecurityHeaders;
+pub use served_file::ServedFile;
 pub use static_handler::Static;
+pub use validators::Validators;
+pub use watch::{Watch, Watcher};
+#[cfg(feature = "cache")]
+pub use cache_policy::CachePolicy;
 #[cfg(feature = "cache")]
 pub use static_handler::Cache;
+#[cfg(feature = "mmap")]
+pub use mmap::MmapCache;
+#[cfg(feature = "reload")]
+pub use reload::{ConfigReloader, Reloading};
+#[cfg(feature = "auth")]
+pub use basic_auth::{BasicAuth, Htpasswd};
 
+mod access_log;
+mod authorizer;
+mod autoindex;
+#[cfg(feature = "auth")]
+mod basic_auth;
+#[cfg(feature = "cache")]
+mod cache_policy;
+#[cfg(feature = "gzip")]
+mod compression;
+#[cfg(feature = "config")]
+mod config;
+mod cors;
+mod file_body;
+mod file_id;
+mod hot_swap;
+mod live_reload;
+mod manifest;
+mod matcher;
+mod metrics;
+mod mime_types;
+#[cfg(feature = "mmap")]
+mod mmap;
+mod path_pattern;
+#[cfg(feature = "reload")]
+mod reload;
 mod requested_path;
+mod security_headers;
+mod served_file;
+mod sniff;
 mod static_handler;
+mod validators;
+mod watch;
@@ -0,0 +1,218 @@
+use std::io::{self, Write};
+use std::path::{Component, Path};
+use std::sync::{Arc, Mutex};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::time::Duration;
+
+use iron::headers::{CacheControl, CacheDirective, ContentType};
+use iron::method::Method;
+use iron::prelude::*;
+use iron::response::WriteBody;
+use iron::status;
+use mount::OriginalUrl;
+
+use watch::{Watch, Watcher};
+
+/// A modifier for `Static` to reload pages in the browser when files under the root change,
+/// for development.
+///
+/// A small script is added to every HTML page served, before `</body>`, which listens to
+/// Server-Sent Events from the handler's `endpoint`. Whenever files under the root change,
+/// pages are reloaded, except that changed stylesheets are swapped in place without a reload.
+/// The root is watched as with `Static::watch`, from the first time a page connects.
+///
+/// Every open page keeps a connection, and with it one of Iron's threads, busy, so this is not
+/// meant for production.
+///
+/// ## Example
+///
+/// ```ignore
+/// let static_handler = Static::new("public").set(LiveReload::new());
+/// ```
+#[derive(Clone)]
+pub struct LiveReload {
+    endpoint: String,
+    watch: Watch,
+    heartbeat: Duration,
+    subscribers: Arc<Mutex<Vec<Sender<Event>>>>,
+    watcher: Arc<Mutex<Option<Watcher>>>,
+}
+
+#[derive(Clone)]
+enum Event {
+    Reload,
+    Stylesheet(String),
+}
+
+impl LiveReload {
+    /// Serve events at `/__staticfile/live-reload` below where the handler is mounted.
+    pub fn new() -> LiveReload {
+        LiveReload {
+            endpoint: "__staticfile/live-reload".to_owned(),
+            watch: Watch::new(),
+            heartbeat: Duration::from_secs(15),
+            subscribers: Arc::default(),
+            watcher: Arc::default(),
+        }
+    }
+
+    /// Serve events at `path` below where the handler is mounted. It hides any file at the
+    /// same path.
+    pub fn endpoint(mut self, path: &str) -> LiveReload {
+        self.endpoint = path.trim_matches('/').to_owned();
+        self
+    }
+
+    /// Watch the root with `watch`, e.g. to scan it on filesystems where inotify doesn't work.
+    pub fn watch(mut self, watch: Watch) -> LiveReload {
+        self.watch = watch;
+        self
+    }
+
+    /// Send a comment every `heartbeat` while nothing changes, so that connections of closed
+    /// pages are noticed and dropped. 15 seconds unless changed.
+    pub fn heartbeat(mut self, heartbeat: Duration) -> LiveReload {
+        self.heartbeat = heartbeat;
+        self
+    }
+
+    /// Returns `true` if `req` is for the event endpoint.
+    pub(crate) fn is_endpoint(&self, req: &Request) -> bool {
+        req.url.path().join("/") == self.endpoint
+    }
+
+    /// Answer a request for the event endpoint, starting to watch the root with `start` if
+    /// no page has connected before.
+    pub(crate) fn events<F>(&self, req: &Request, start: F) -> IronResult<Response>
+        where F: FnOnce(Watch) -> io::Result<Watcher>
+    {
+        if req.method != Method::Get {
+            return Ok(Response::with(status::MethodNotAllowed));
+        }
+
+        {
+            let mut watcher = self.watcher.lock().unwrap();
+            if watcher.is_none() {
+                // The watcher's thread must not own the watcher, or it could end up joining itself.
+                let subscribers = self.subscribers.clone();
+                let watch = self.watch.clone().on_change(move |path| {
+                    let event = Event::for_change(path);
+                    // Pages that went away are dropped as soon as their stream notices.
+                    subscribers.lock().unwrap().retain(|subscriber| subscriber.send(event.clone()).is_ok());
+                });
+                *watcher = Some(start(watch).map_err(|e| IronError::new(e, status::InternalServerError))?);
+            }
+        }
+
+        let (sender, events) = mpsc::channel();
+        self.subscribers.lock().unwrap().push(sender);
+
+        let mut response = Response::with(status::Ok);
+        response.body = Some(Box::new(EventStream { events, heartbeat: self.heartbeat }));
+        response.headers.set(ContentType("text/event-stream".parse().unwrap()));
+        response.headers.set(CacheControl(vec![CacheDirective::NoCache]));
+        Ok(response)
+    }
+
+    /// Add the script to an HTML page.
+    pub(crate) fn inject(&self, req: &Request, mut page: Vec<u8>) -> Vec<u8> {
+        let script = self.script(req);
+        let at = rfind_ignore_case(&page, b"</body>").unwrap_or(page.len());
+        page.splice(at..at, script.into_bytes());
+        page
+    }
+
+    fn script(&self, req: &Request) -> String {
+        // The endpoint is below the handler, wherever it is mounted.
+        let original = req.extensions.get::<OriginalUrl>().unwrap_or(&req.url).path();
+        let prefix = &original[..original.len().saturating_sub(req.url.path().len())];
+        let mut url = String::new();
+        for segment in prefix.iter().chain(&[&self.endpoint[..]]) {
+            url.push('/');
+            url.push_str(segment);
+        }
+        format!("<script>{}</script>\n", SCRIPT.replace("ENDPOINT", &js_string(&url)))
+    }
+}
+
+impl Default for LiveReload {
+    fn default() -> LiveReload {
+        LiveReload::new()
+    }
+}
+
+impl Event {
+    fn for_change(path: &Path) -> Event {
+        let is_css = path.extension().is_some_and(|extension| extension.eq_ignore_ascii_case("css"));
+        let parts: Option<Vec<&str>> = path.components().map(|component| match component {
+            Component::Normal(part) => part.to_str(),
+            _ => None,
+        }).collect();
+        match parts {
+            Some(ref parts) if is_css => Event::Stylesheet(parts.join("/")),
+            _ => Event::Reload,
+        }
+    }
+}
+
+/// The body of an event endpoint response, which lasts until the page goes away.
+struct EventStream {
+    events: Receiver<Event>,
+    heartbeat: Duration,
+}
+
+impl WriteBody for EventStream {
+    fn write_body(&mut self, res: &mut dyn Write) -> io::Result<()> {
+        res.write_all(b"retry: 1000\n\n")?;
+        res.flush()?;
+        loop {
+            // Each event is written at once, so that it isn't split across chunks.
+            let event = match self.events.recv_timeout(self.heartbeat) {
+                Ok(Event::Reload) => "event: reload\ndata:\n\n".to_owned(),
+                Ok(Event::Stylesheet(path)) => format!("event: css\ndata: {}\n\n", path),
+                Err(RecvTimeoutError::Timeout) => ": heartbeat\n\n".to_owned(),
+                Err(RecvTimeoutError::Disconnected) => return Ok(()),
+            };
+            res.write_all(event.as_bytes())?;
+            res.flush()?;
+        }
+    }
+}
+
+/// A JavaScript string literal for `value`, safe to put in a `<script>` element.
+fn js_string(value: &str) -> String {
+    let mut literal = String::from("\"");
+    for c in value.chars() {
+        match c {
+            '"' | '\\' | '<' | '>' | '&' => literal.push_str(&format!("\\u{:04x}", c as u32)),
+            c => literal.push(c),
+        }
+    }
+    literal.push('"');
+    literal
+}
+
+fn rfind_ignore_case(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    (0..(haystack.len() + 1).saturating_sub(needle.len()))
+        .rev()
+        .find(|&at| haystack[at..at + needle.len()].eq_ignore_ascii_case(needle))
+}
+
+/// Reloads the page on `reload` events, and reloads the stylesheets whose path ends with the
+/// changed file's, or all of them if none does, on `css` events.
+const SCRIPT: &str = "(function () {
+  var source = new EventSource(ENDPOINT);
+  source.addEventListener('reload', function () { location.reload(); });
+  source.addEventListener('css', function (event) {
+    var links = [].slice.call(document.querySelectorAll('link[rel=\"stylesheet\"]'));
+    var changed = links.filter(function (link) {
+      var path = new URL(link.href).pathname;
+      return path.slice(-event.data.length - 1) === '/' + event.data;
+    });
+    (changed.length ? changed : links).forEach(function (link) {
+      var url = new URL(link.href);
+      url.searchParams.set('livereload', Date.now());
+      link.href = url.href;
+    });
+  });
+})();";
@@ -0,0 +1,311 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fs::{self, File};
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+
+use file_id::Visited;
+
+/// What `Static` does with a request for a hashed URL whose hash is no longer current.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OutdatedAssets {
+    /// Answer with `404 Not Found`, so that stale pages fail loudly.
+    NotFound,
+    /// Redirect to the current hashed URL with `302 Found`.
+    Redirect,
+}
+
+/// A manifest mapping the files below a root to URLs containing a hash of their content.
+///
+/// `app.js` is given a URL like `/app.3f9a2c1b.js`, which `Static` serves from the original
+/// file with `Cache-Control: public, max-age=31536000, immutable`, since changed content gets
+/// a new URL. `asset_url` looks up the URL of a file, for use in templates. The URLs are
+/// relative to where the handler is mounted.
+///
+/// The manifest is built when it is created with `build`, or on first use with `on_demand`.
+/// It is built again on `rebuild`, and `Static::watch` rehashes only the files that changed.
+/// It is cheap to clone and clones share their entries, so a handle can be kept for templates
+/// after setting the manifest on a `Static`.
+///
+/// ## Example
+///
+/// ```ignore
+/// let manifest = AssetManifest::build(path)?.outdated(OutdatedAssets::Redirect);
+/// let static_handler = Static::new(path).set(manifest.clone());
+/// let script = manifest.asset_url("app.js"); // Some("/app.3f9a2c1b.js")
+/// ```
+#[derive(Clone, Debug)]
+pub struct AssetManifest {
+    root: PathBuf,
+    entries: Arc<RwLock<Option<Entries>>>,
+    outdated: OutdatedAssets,
+}
+
+#[derive(Debug, Default)]
+struct Entries {
+    /// Hashed paths by original path, both relative to the root with `/` separators.
+    hashed: HashMap<String, String>,
+    /// Original paths by hashed path.
+    original: HashMap<String, String>,
+}
+
+/// How a path relative to the root relates to the manifest.
+pub enum Asset {
+    /// The current hashed path of the given original file.
+    Current(String),
+    /// An outdated hashed path of the given original file, whose current hashed path is the
+    /// second one.
+    Outdated(String, String),
+}
+
+impl AssetManifest {
+    /// Hash every file below `root` now.
+    pub fn build<P: Into<PathBuf>>(root: P) -> io::Result<AssetManifest> {
+        let manifest = AssetManifest::on_demand(root);
+        manifest.rebuild()?;
+        Ok(manifest)
+    }
+
+    /// Hash the files below `root` when the manifest is first used.
+    pub fn on_demand<P: Into<PathBuf>>(root: P) -> AssetManifest {
+        AssetManifest {
+            root: root.into(),
+            entries: Arc::new(RwLock::new(None)),
+            outdated: OutdatedAssets::NotFound,
+        }
+    }
+
+    /// Set what happens to requests for outdated hashed URLs. `OutdatedAssets::NotFound` unless
+    /// changed.
+    pub fn outdated(mut self, outdated: OutdatedAssets) -> AssetManifest {
+        self.outdated = outdated;
+        self
+    }
+
+    /// Hash the files below the root again, e.g. after a deployment.
+    pub fn rebuild(&self) -> io::Result<()> {
+        let entries = Entries::build(&self.root)?;
+        *self.entries.write().unwrap() = Some(entries);
+        Ok(())
+    }
+
+    /// The hashed URL of the file at `path`, such as `"app.js"` or `"/css/site.css"`, relative
+    /// to the root.
+    ///
+    /// Returns `None` if the file isn't in the manifest, or if an on-demand manifest can't be
+    /// built.
+    pub fn asset_url(&self, path: &str) -> Option<String> {
+        let path = path.trim_start_matches('/');
+        self.with_entries(|entries| entries.hashed.get(path).map(|hashed| format!("/{}", hashed)))
+            .ok()
+            .and_then(|url| url)
+    }
+
+    /// All hashed URLs by the path of their file, both relative to the root, e.g. to write out
+    /// as `manifest.json` for other tools.
+    pub fn urls(&self) -> io::Result<BTreeMap<String, String>> {
+        self.with_entries(|entries| {
+            entries.hashed.iter()
+                .map(|(path, hashed)| (format!("/{}", path), format!("/{}", hashed)))
+                .collect()
+        })
+    }
+
+    /// Hash the file or directory at `path` again after it changed, or drop it from the
+    /// manifest if it no longer exists. A manifest that hasn't been built yet is left alone.
+    pub(crate) fn refresh(&self, path: &Path) {
+        let relative = match path.strip_prefix(&self.root).ok().and_then(to_key) {
+            Some(ref relative) if relative.is_empty() => return self.reset(),
+            Some(relative) => relative,
+            None => return,
+        };
+        if self.entries.read().unwrap().is_none() {
+            return;
+        }
+
+        // Hash outside the lock, so requests aren't held up.
+        let mut fresh = Entries::default();
+        let mut visited = Visited::new();
+        let added = match fs::metadata(path) {
+            Ok(ref metadata) if metadata.is_dir() => fresh.add_dir(&self.root, path, &mut visited),
+            Ok(ref metadata) if metadata.is_file() => fresh.add_file(&self.root, path),
+            Ok(_) => Ok(()),
+            Err(ref e) if e.kind() == io::ErrorKind::NotFound => Ok(()),
+            Err(e) => Err(e),
+        };
+
+        let mut entries = self.entries.write().unwrap();
+        match (added, entries.as_mut()) {
+            (Ok(()), Some(entries)) => {
+                entries.remove_under(&relative);
+                entries.original.extend(fresh.original);
+                entries.hashed.extend(fresh.hashed);
+            },
+            // The file changed again while it was hashed, so start over on next use.
+            (Err(_), Some(_)) => *entries = None,
+            (_, None) => {},
+        }
+    }
+
+    /// Forget all entries, to be built again on next use.
+    pub(crate) fn reset(&self) {
+        *self.entries.write().unwrap() = None;
+    }
+
+    pub(crate) fn outdated_assets(&self) -> OutdatedAssets {
+        self.outdated
+    }
+
+    /// Look up a requested path relative to the root.
+    pub(crate) fn lookup(&self, relative: &Path) -> io::Result<Option<Asset>> {
+        let relative = match to_key(relative) {
+            Some(relative) => relative,
+            None => return Ok(None),
+        };
+
+        self.with_entries(|entries| {
+            // A real file is served as itself, even if its name looks hashed.
+            if entries.hashed.contains_key(&relative) {
+                return None;
+            }
+            if let Some(original) = entries.original.get(&relative) {
+                return Some(Asset::Current(original.clone()));
+            }
+            strip_hash(&relative)
+                .and_then(|original| entries.hashed.get(&original).map(|hashed| (original, hashed)))
+                .map(|(original, hashed)| Asset::Outdated(original, hashed.clone()))
+        })
+    }
+
+    fn with_entries<T, F: FnOnce(&Entries) -> T>(&self, f: F) -> io::Result<T> {
+        if let Some(ref entries) = *self.entries.read().unwrap() {
+            return Ok(f(entries));
+        }
+
+        let mut entries = self.entries.write().unwrap();
+        if entries.is_none() {
+            *entries = Some(Entries::build(&self.root)?);
+        }
+        Ok(f(entries.as_ref().unwrap()))
+    }
+}
+
+impl Entries {
+    fn build(root: &Path) -> io::Result<Entries> {
+        let mut entries = Entries::default();
+        entries.add_dir(root, root, &mut Visited::new())?;
+        Ok(entries)
+    }
+
+    fn add_dir(&mut self, root: &Path, dir: &Path, visited: &mut Visited) -> io::Result<()> {
+        if !visited.enter(dir, &fs::metadata(dir)?) {
+            return Ok(());
+        }
+        for entry in fs::read_dir(dir)?.filter_map(Result::ok) {
+            let path = entry.path();
+            // Symlinked files are served too, so they get hashed URLs. Dangling links and entries
+            // that can't be read can't be served, and must not keep the rest from being hashed.
+            let added = match fs::metadata(&path) {
+                Ok(ref metadata) if metadata.is_dir() => self.add_dir(root, &path, visited),
+                Ok(ref metadata) if metadata.is_file() => self.add_file(root, &path),
+                Ok(_) | Err(_) => continue,
+            };
+            if let Err(e) = added {
+                if !matches!(e.kind(), io::ErrorKind::NotFound | io::ErrorKind::PermissionDenied) {
+                    return Err(e);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn add_file(&mut self, root: &Path, path: &Path) -> io::Result<()> {
+        if let Some(key) = path.strip_prefix(root).ok().and_then(to_key) {
+            let hashed = hashed_name(&key, &hash_file(path)?);
+            self.original.insert(hashed.clone(), key.clone());
+            self.hashed.insert(key, hashed);
+        }
+        Ok(())
+    }
+
+    /// Remove the file `key`, or every file below the directory `key`.
+    fn remove_under(&mut self, key: &str) {
+        let dir = format!("{}/", key);
+        let original = &mut self.original;
+        self.hashed.retain(|path, hashed| {
+            let keep = path != key && !path.starts_with(&dir);
+            if !keep {
+                original.remove(hashed);
+            }
+            keep
+        });
+    }
+}
+
+/// A relative path with `/` separators, or `None` if it isn't valid UTF-8.
+fn to_key(relative: &Path) -> Option<String> {
+    let parts: Option<Vec<&str>> = relative.iter().map(|part| part.to_str()).collect();
+    parts.map(|parts| parts.join("/"))
+}
+
+/// Insert `hash` before the extension of the file name: `css/site.css` becomes
+/// `css/site.<hash>.css`.
+fn hashed_name(key: &str, hash: &str) -> String {
+    let name_start = key.rfind('/').map_or(0, |slash| slash + 1);
+    match key[name_start..].rfind('.') {
+        Some(dot) if dot > 0 => {
+            let dot = name_start + dot;
+            format!("{}.{}{}", &key[..dot], hash, &key[dot..])
+        },
+        _ => format!("{}.{}", key, hash),
+    }
+}
+
+/// The inverse of `hashed_name` for any hash, or `None` if `key` doesn't contain one.
+fn strip_hash(key: &str) -> Option<String> {
+    let name_start = key.rfind('/').map_or(0, |slash| slash + 1);
+    let name = &key[name_start..];
+    let parts: Vec<&str> = name.split('.').collect();
+
+    // Names without an extension get the hash appended, others get it before the extension.
+    let index = match parts.len() {
+        0 | 1 => return None,
+        2 => 1,
+        n => n - 2,
+    };
+    if parts[index].len() != HASH_LEN || !parts[index].bytes().all(|b| b.is_ascii_hexdigit()) {
+        return None;
+    }
+
+    let mut original = parts;
+    original.remove(index);
+    Some(format!("{}{}", &key[..name_start], original.join(".")))
+}
+
+/// The number of hex digits of the content hash used in URLs.
+const HASH_LEN: usize = 8;
+
+/// A hash of the content of a file as `HASH_LEN` hex digits.
+///
+/// This is FNV-1a, which is stable across builds and platforms so that every server of a
+/// deployment gives a file the same URL. It only needs to tell versions of a file apart, not
+/// to resist collisions crafted on purpose.
+fn hash_file(path: &Path) -> io::Result<String> {
+    const OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+    const PRIME: u64 = 0x0000_0100_0000_01b3;
+
+    let mut file = File::open(path)?;
+    let mut buffer = [0; 64 * 1024];
+    let mut hash = OFFSET;
+    loop {
+        let read = file.read(&mut buffer)?;
+        if read == 0 {
+            break;
+        }
+        for &byte in &buffer[..read] {
+            hash = (hash ^ u64::from(byte)).wrapping_mul(PRIME);
+        }
+    }
+
+    Ok(format!("{:016x}", hash)[..HASH_LEN].to_owned())
+}
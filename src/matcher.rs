@@ -0,0 +1,59 @@
+use std::path::Path;
+
+use iron::mime::Mime;
+
+use path_pattern::PathPattern;
+
+/// A pattern such as `text/html` or `image/*` matched against the type a file is served as.
+///
+/// Either part may be `*`, and parameters such as `charset` are ignored.
+#[derive(Clone, Debug)]
+pub struct MimePattern {
+    top: String,
+    sub: String,
+}
+
+impl MimePattern {
+    /// Parse a pattern, or return `None` if it is not of the form `type/subtype`.
+    pub fn new(pattern: &str) -> Option<MimePattern> {
+        let mut parts = pattern.splitn(2, '/');
+        match (parts.next(), parts.next()) {
+            (Some(top), Some(sub)) if !top.trim().is_empty() && !sub.trim().is_empty() => Some(MimePattern {
+                top: top.trim().to_lowercase(),
+                sub: sub.trim().to_lowercase(),
+            }),
+            _ => None,
+        }
+    }
+
+    /// ## Panics
+    ///
+    /// Panics if `pattern` is not of the form `type/subtype`.
+    pub fn expect(pattern: &str) -> MimePattern {
+        match MimePattern::new(pattern) {
+            Some(pattern) => pattern,
+            None => panic!("Invalid MIME pattern {:?}", pattern),
+        }
+    }
+
+    pub fn matches(&self, mime: &Mime) -> bool {
+        (self.top == "*" || mime.0.as_str().eq_ignore_ascii_case(&self.top))
+            && (self.sub == "*" || mime.1.as_str().eq_ignore_ascii_case(&self.sub))
+    }
+}
+
+/// Selects served files by their path relative to the root or by their type.
+#[derive(Clone, Debug)]
+pub enum Matcher {
+    Glob(PathPattern),
+    Mime(MimePattern),
+}
+
+impl Matcher {
+    pub fn matches(&self, relative: &Path, mime: &Mime) -> bool {
+        match *self {
+            Matcher::Glob(ref pattern) => pattern.matches(relative),
+            Matcher::Mime(ref pattern) => pattern.matches(mime),
+        }
+    }
+}
@@ -0,0 +1,213 @@
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::{Arc, Mutex};
+
+use iron::headers::{CacheControl, CacheDirective, ContentType};
+use iron::prelude::*;
+use iron::{status, Handler};
+
+use access_log::{AccessLog, AccessLogEntry};
+
+/// Receives the measurements of a `Static`, to keep in any metrics registry.
+///
+/// Every request adds to these metrics once its response has been sent:
+///
+/// - `staticfile_requests_total`, a counter labelled with the status `class`, such as `2xx`
+/// - `staticfile_sent_bytes_total`, the bytes of bodies sent, after compression
+/// - `staticfile_not_modified_total`, the requests answered `304 Not Modified`; divided by the
+///   requests, this is how often clients' copies were still current
+/// - `staticfile_range_requests_total`, the requests with a `Range` header
+/// - `staticfile_compressed_responses_total`, labelled with the `encoding`, and
+///   `staticfile_compression_ratio`, a histogram of the compressed size of bodies divided by
+///   their original size
+/// - `staticfile_cache_hits_total` and `staticfile_cache_misses_total`, the files sent from an
+///   existing `MmapCache` mapping or mapped for the request
+/// - `staticfile_request_duration_seconds`, a histogram of the time until the response was sent
+///
+/// `MetricsRegistry` keeps them in memory and serves them in the Prometheus text format.
+pub trait Metrics: Send + Sync + 'static {
+    /// Add `value` to the counter `name` with `labels`.
+    fn counter(&self, name: &'static str, labels: &[(&'static str, &str)], value: u64);
+
+    /// Record `value` in the histogram `name` with `labels`.
+    fn histogram(&self, name: &'static str, labels: &[(&'static str, &str)], value: f64);
+}
+
+/// Reports the requests logged by `Static` to its `Metrics`.
+pub(crate) struct Recorder<M>(pub M);
+
+impl<M: Metrics> AccessLog for Recorder<M> {
+    fn log(&self, entry: &AccessLogEntry) {
+        let metrics = &self.0;
+        let class = format!("{}xx", entry.status.to_u16() / 100);
+        metrics.counter("staticfile_requests_total", &[("class", &class)], 1);
+        metrics.counter("staticfile_sent_bytes_total", &[], entry.bytes);
+        if entry.not_modified {
+            metrics.counter("staticfile_not_modified_total", &[], 1);
+        }
+        if entry.range.is_some() {
+            metrics.counter("staticfile_range_requests_total", &[], 1);
+        }
+        if let Some(ref encoding) = entry.encoding {
+            metrics.counter("staticfile_compressed_responses_total", &[("encoding", encoding)], 1);
+            // A HEAD response or an aborted download says nothing about the ratio.
+            match entry.length {
+                Some(length) if length > 0 && entry.bytes > 0 => {
+                    let ratio = entry.bytes as f64 / length as f64;
+                    metrics.histogram("staticfile_compression_ratio", &[("encoding", encoding)], ratio);
+                },
+                _ => {},
+            }
+        }
+        match entry.cache_hit {
+            Some(true) => metrics.counter("staticfile_cache_hits_total", &[], 1),
+            Some(false) => metrics.counter("staticfile_cache_misses_total", &[], 1),
+            None => {},
+        }
+        metrics.histogram("staticfile_request_duration_seconds", &[], entry.latency.as_secs_f64());
+    }
+}
+
+/// `Metrics` kept in memory, and a `Handler` serving them in the Prometheus text format.
+///
+/// Clones share the same metrics, so one clone can be given to each `Static` and another
+/// mounted where Prometheus scrapes.
+///
+/// ## Example
+///
+/// ```ignore
+/// let registry = MetricsRegistry::new();
+/// let mut mount = Mount::new();
+/// mount.mount("/", Static::new("public").metrics(registry.clone()));
+/// mount.mount("/metrics", registry);
+/// ```
+#[derive(Clone)]
+pub struct MetricsRegistry {
+    inner: Arc<Mutex<Registry>>,
+}
+
+#[derive(Default)]
+struct Registry {
+    buckets: BTreeMap<String, Vec<f64>>,
+    counters: BTreeMap<String, BTreeMap<String, u64>>,
+    histograms: BTreeMap<String, BTreeMap<String, Histogram>>,
+}
+
+struct Histogram {
+    /// The upper bounds of the buckets, and how many values were at most each.
+    buckets: Vec<(f64, u64)>,
+    sum: f64,
+    count: u64,
+}
+
+impl MetricsRegistry {
+    /// An empty registry, with buckets for durations from half a millisecond to 10 seconds and
+    /// for ratios in steps of a tenth.
+    pub fn new() -> MetricsRegistry {
+        MetricsRegistry { inner: Arc::default() }
+            .buckets("staticfile_compression_ratio", RATIO_BUCKETS)
+    }
+
+    /// Count the values of the histogram `name` in buckets with the upper bounds `bounds`,
+    /// instead of the buckets for durations.
+    pub fn buckets(self, name: &str, bounds: &[f64]) -> MetricsRegistry {
+        let mut bounds = bounds.to_vec();
+        bounds.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        self.inner.lock().unwrap().buckets.insert(name.to_owned(), bounds);
+        self
+    }
+
+    /// The metrics in the Prometheus text exposition format.
+    pub fn render(&self) -> String {
+        let registry = self.inner.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        let mut text = String::new();
+        for (name, series) in &registry.counters {
+            let _ = writeln!(text, "# TYPE {} counter", name);
+            for (labels, value) in series {
+                let _ = writeln!(text, "{}{} {}", name, braced(labels), value);
+            }
+        }
+        for (name, series) in &registry.histograms {
+            let _ = writeln!(text, "# TYPE {} histogram", name);
+            for (labels, histogram) in series {
+                let separator = if labels.is_empty() { "" } else { "," };
+                for &(bound, count) in &histogram.buckets {
+                    let _ = writeln!(text, "{}_bucket{{{}{}le=\"{}\"}} {}", name, labels, separator, bound, count);
+                }
+                let _ = writeln!(text, "{}_bucket{{{}{}le=\"+Inf\"}} {}", name, labels, separator, histogram.count);
+                let _ = writeln!(text, "{}_sum{} {}", name, braced(labels), histogram.sum);
+                let _ = writeln!(text, "{}_count{} {}", name, braced(labels), histogram.count);
+            }
+        }
+        text
+    }
+}
+
+impl Default for MetricsRegistry {
+    fn default() -> MetricsRegistry {
+        MetricsRegistry::new()
+    }
+}
+
+impl Metrics for MetricsRegistry {
+    fn counter(&self, name: &'static str, labels: &[(&'static str, &str)], value: u64) {
+        let mut registry = self.inner.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        *registry.counters.entry(name.to_owned()).or_default().entry(label_set(labels)).or_insert(0) += value;
+    }
+
+    fn histogram(&self, name: &'static str, labels: &[(&'static str, &str)], value: f64) {
+        let mut registry = self.inner.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        let registry = &mut *registry;
+        let bounds = registry.buckets.get(name).map_or(DURATION_BUCKETS, |bounds| &bounds[..]);
+        let histogram = registry.histograms.entry(name.to_owned()).or_default()
+            .entry(label_set(labels))
+            .or_insert_with(|| Histogram {
+                buckets: bounds.iter().map(|&bound| (bound, 0)).collect(),
+                sum: 0.0,
+                count: 0,
+            });
+        for bucket in histogram.buckets.iter_mut().filter(|&&mut (bound, _)| value <= bound) {
+            bucket.1 += 1;
+        }
+        histogram.sum += value;
+        histogram.count += 1;
+    }
+}
+
+impl Handler for MetricsRegistry {
+    fn handle(&self, _: &mut Request) -> IronResult<Response> {
+        let mut response = Response::with((status::Ok, self.render()));
+        response.headers.set(ContentType("text/plain; version=0.0.4; charset=utf-8".parse().unwrap()));
+        response.headers.set(CacheControl(vec![CacheDirective::NoStore]));
+        Ok(response)
+    }
+}
+
+/// The labels of a series as they appear between its braces, e.g. `class="2xx"`.
+fn label_set(labels: &[(&'static str, &str)]) -> String {
+    let mut set = String::new();
+    for (i, &(name, value)) in labels.iter().enumerate() {
+        if i > 0 {
+            set.push(',');
+        }
+        let _ = write!(set, "{}=\"", name);
+        for c in value.chars() {
+            match c {
+                '\\' => set.push_str("\\\\"),
+                '"' => set.push_str("\\\""),
+                '\n' => set.push_str("\\n"),
+                c => set.push(c),
+            }
+        }
+        set.push('"');
+    }
+    set
+}
+
+fn braced(labels: &str) -> String {
+    if labels.is_empty() { String::new() } else { format!("{{{}}}", labels) }
+}
+
+const DURATION_BUCKETS: &[f64] = &[0.0005, 0.001, 0.0025, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0];
+
+const RATIO_BUCKETS: &[f64] = &[0.1, 0.2, 0.3, 0.4, 0.5, 0.6, 0.7, 0.8, 0.9, 1.0];
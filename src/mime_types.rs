@@ -0,0 +1,334 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::path::Path;
+
+use iron::mime::{Attr, Mime, TopLevel, SubLevel, Value};
+
+use path_pattern::PathPattern;
+use requested_path::ResolvedFile;
+use sniff::{bom_charset, read_head, sniff};
+
+/// A modifier for `Static` to choose the `Content-Type` of served files.
+///
+/// `MimeTypes::new()` knows the common types of the web platform. Extensions can be added or
+/// remapped with `extension`, and `glob` assigns a type to every file whose path relative to
+/// the root matches a pattern, taking precedence over extensions. Globs are tried in the
+/// order they were added. Files matching neither are served as `default_type`, `text/plain`
+/// unless changed.
+///
+/// With `sniff` enabled, such files are instead identified by their first bytes if possible
+/// (PNG, JPEG, GIF, PDF, gzip, zip and UTF-8 text), and every response is sent with
+/// `X-Content-Type-Options: nosniff` so that browsers keep the type chosen here.
+///
+/// A `default_charset` is appended to textual types (`text/*`, `application/javascript` and
+/// `application/json`) that don't specify one, and `charset` sets it for a single extension
+/// whatever its type. With `detect_bom` enabled, a byte order mark at the start of a textual
+/// file takes precedence over both.
+///
+/// ## Example
+///
+/// ```ignore
+/// let types = MimeTypes::new()
+///     .extension("tpl", "text/html".parse().unwrap())
+///     .glob("/downloads/*", "application/octet-stream".parse().unwrap());
+/// let static_handler = Static::new(path).set(types);
+/// ```
+#[derive(Clone, Debug)]
+pub struct MimeTypes {
+    extensions: HashMap<String, Mime>,
+    globs: Vec<(PathPattern, Mime)>,
+    default: Mime,
+    sniff: bool,
+    default_charset: Option<String>,
+    charsets: HashMap<String, String>,
+    detect_bom: bool,
+}
+
+impl MimeTypes {
+    /// Create a table of the built-in extension mappings.
+    pub fn new() -> MimeTypes {
+        let extensions = BUILT_IN.iter()
+            .map(|&(ext, mime)| (ext.to_owned(), mime.parse().unwrap()))
+            .collect();
+
+        MimeTypes {
+            extensions,
+            globs: Vec::new(),
+            default: Mime(TopLevel::Text, SubLevel::Plain, vec![]),
+            sniff: false,
+            default_charset: None,
+            charsets: HashMap::new(),
+            detect_bom: false,
+        }
+    }
+
+    /// Serve files with the given extension, compared case-insensitively, as `mime`.
+    pub fn extension(mut self, extension: &str, mime: Mime) -> MimeTypes {
+        self.extensions.insert(extension.trim_start_matches('.').to_lowercase(), mime);
+        self
+    }
+
+    /// Serve files whose path relative to the root matches `pattern` as `mime`.
+    ///
+    /// ## Panics
+    ///
+    /// Panics if `pattern` is not a valid glob.
+    pub fn glob(mut self, pattern: &str, mime: Mime) -> MimeTypes {
+        self.globs.push((PathPattern::expect(pattern), mime));
+        self
+    }
+
+    /// Set the type of files that no extension or glob applies to.
+    pub fn default_type(mut self, mime: Mime) -> MimeTypes {
+        self.default = mime;
+        self
+    }
+
+    /// Identify files that no extension or glob applies to by their content.
+    pub fn sniff(mut self, sniff: bool) -> MimeTypes {
+        self.sniff = sniff;
+        self
+    }
+
+    /// Append `charset` to textual types that don't specify one, e.g. `"utf-8"`.
+    pub fn default_charset(mut self, charset: &str) -> MimeTypes {
+        self.default_charset = Some(charset.to_owned());
+        self
+    }
+
+    /// Serve files with the given extension, compared case-insensitively, with `charset`.
+    pub fn charset(mut self, extension: &str, charset: &str) -> MimeTypes {
+        self.charsets.insert(extension.trim_start_matches('.').to_lowercase(), charset.to_owned());
+        self
+    }
+
+    /// Take the charset of textual files from their byte order mark, if they start with one.
+    pub fn detect_bom(mut self, detect_bom: bool) -> MimeTypes {
+        self.detect_bom = detect_bom;
+        self
+    }
+
+    pub(crate) fn sniffs(&self) -> bool {
+        self.sniff
+    }
+
+    /// Look up the type of a file by its path relative to the root, without falling back to
+    /// the default type.
+    pub fn lookup(&self, relative: &Path) -> Option<Mime> {
+        self.find(relative, relative).cloned()
+    }
+
+    /// The type of a resolved file whose path is `relative` to the root.
+    ///
+    /// The extension is taken from the full path, so that a root pointing at a single file
+    /// still gets its type.
+    pub(crate) fn mime_for(&self, file: &ResolvedFile, relative: &Path) -> Mime {
+        let mut head = Head { file: file.file.as_ref(), path: &file.path, bytes: None };
+
+        let mime = match self.find(&file.path, relative) {
+            Some(mime) => mime.clone(),
+            None if self.sniff => sniff(head.bytes()).unwrap_or_else(|| self.default.clone()),
+            None => self.default.clone(),
+        };
+
+        self.with_charset(mime, &file.path, &mut head)
+    }
+
+    fn with_charset(&self, mut mime: Mime, path: &Path, head: &mut Head) -> Mime {
+        if mime.get_param(Attr::Charset).is_some() {
+            return mime;
+        }
+
+        let textual = is_textual(&mime);
+        let charset = if textual && self.detect_bom {
+            bom_charset(head.bytes())
+        } else {
+            None
+        };
+        let charset = charset
+            .or_else(|| {
+                path.extension()
+                    .and_then(|ext| ext.to_str())
+                    .and_then(|ext| self.charsets.get(&ext.to_lowercase()))
+                    .map(|charset| &charset[..])
+            })
+            .or_else(|| if textual { self.default_charset.as_ref().map(|c| &c[..]) } else { None });
+
+        if let Some(charset) = charset {
+            let value = if charset.eq_ignore_ascii_case("utf-8") {
+                Value::Utf8
+            } else {
+                Value::Ext(charset.to_owned())
+            };
+            mime.2.push((Attr::Charset, value));
+        }
+
+        mime
+    }
+
+    fn find(&self, path: &Path, relative: &Path) -> Option<&Mime> {
+        self.globs.iter()
+            .find(|(pattern, _)| pattern.matches(relative))
+            .map(|(_, mime)| mime)
+            .or_else(|| {
+                path.extension()
+                    .and_then(|ext| ext.to_str())
+                    .and_then(|ext| self.extensions.get(&ext.to_lowercase()))
+            })
+    }
+}
+
+/// The first bytes of a file, read at most once and only if needed.
+///
+/// Files resolved without a handle, as for `HEAD` requests, are opened here so that their
+/// type matches the one a `GET` would send.
+struct Head<'a> {
+    file: Option<&'a File>,
+    path: &'a Path,
+    bytes: Option<Vec<u8>>,
+}
+
+impl<'a> Head<'a> {
+    fn bytes(&mut self) -> &[u8] {
+        if self.bytes.is_none() {
+            let bytes = match self.file {
+                Some(file) => read_head(file),
+                None => File::open(self.path).and_then(|file| read_head(&file)),
+            };
+            self.bytes = Some(bytes.unwrap_or_default());
+        }
+
+        self.bytes.as_ref().unwrap()
+    }
+}
+
+fn is_textual(mime: &Mime) -> bool {
+    matches!(*mime, Mime(TopLevel::Text, _, _)
+                  | Mime(TopLevel::Application, SubLevel::Javascript, _)
+                  | Mime(TopLevel::Application, SubLevel::Json, _))
+}
+
+impl Default for MimeTypes {
+    fn default() -> MimeTypes {
+        MimeTypes::new()
+    }
+}
+
+const BUILT_IN: &[(&str, &str)] = &[
+    // Documents and text
+    ("html", "text/html"),
+    ("htm", "text/html"),
+    ("xhtml", "application/xhtml+xml"),
+    ("css", "text/css"),
+    ("txt", "text/plain"),
+    ("text", "text/plain"),
+    ("md", "text/markdown"),
+    ("markdown", "text/markdown"),
+    ("csv", "text/csv"),
+    ("tsv", "text/tab-separated-values"),
+    ("ics", "text/calendar"),
+    ("vtt", "text/vtt"),
+    ("xml", "application/xml"),
+    ("xsl", "application/xml"),
+    ("rss", "application/rss+xml"),
+    ("atom", "application/atom+xml"),
+    ("pdf", "application/pdf"),
+    ("rtf", "application/rtf"),
+    ("epub", "application/epub+zip"),
+
+    // Scripts and data
+    ("js", "application/javascript"),
+    ("mjs", "application/javascript"),
+    ("cjs", "application/javascript"),
+    ("map", "application/json"),
+    ("json", "application/json"),
+    ("jsonld", "application/ld+json"),
+    ("webmanifest", "application/manifest+json"),
+    ("wasm", "application/wasm"),
+    ("yaml", "application/yaml"),
+    ("yml", "application/yaml"),
+    ("toml", "application/toml"),
+
+    // Images
+    ("png", "image/png"),
+    ("apng", "image/apng"),
+    ("jpg", "image/jpeg"),
+    ("jpeg", "image/jpeg"),
+    ("jfif", "image/jpeg"),
+    ("gif", "image/gif"),
+    ("webp", "image/webp"),
+    ("avif", "image/avif"),
+    ("heic", "image/heic"),
+    ("heif", "image/heif"),
+    ("jxl", "image/jxl"),
+    ("svg", "image/svg+xml"),
+    ("svgz", "image/svg+xml"),
+    ("ico", "image/vnd.microsoft.icon"),
+    ("cur", "image/x-icon"),
+    ("bmp", "image/bmp"),
+    ("tif", "image/tiff"),
+    ("tiff", "image/tiff"),
+
+    // Fonts
+    ("woff", "font/woff"),
+    ("woff2", "font/woff2"),
+    ("ttf", "font/ttf"),
+    ("otf", "font/otf"),
+    ("eot", "application/vnd.ms-fontobject"),
+
+    // Audio and video
+    ("mp3", "audio/mpeg"),
+    ("m4a", "audio/mp4"),
+    ("aac", "audio/aac"),
+    ("oga", "audio/ogg"),
+    ("ogg", "audio/ogg"),
+    ("opus", "audio/ogg"),
+    ("wav", "audio/wav"),
+    ("flac", "audio/flac"),
+    ("weba", "audio/webm"),
+    ("mid", "audio/midi"),
+    ("midi", "audio/midi"),
+    ("mp4", "video/mp4"),
+    ("m4v", "video/mp4"),
+    ("ogv", "video/ogg"),
+    ("webm", "video/webm"),
+    ("mov", "video/quicktime"),
+    ("avi", "video/x-msvideo"),
+    ("mpeg", "video/mpeg"),
+    ("mpg", "video/mpeg"),
+    ("ts", "video/mp2t"),
+    ("m3u8", "application/vnd.apple.mpegurl"),
+    ("mpd", "application/dash+xml"),
+
+    // Archives and binaries
+    ("zip", "application/zip"),
+    ("gz", "application/gzip"),
+    ("tgz", "application/gzip"),
+    ("bz2", "application/x-bzip2"),
+    ("xz", "application/x-xz"),
+    ("zst", "application/zstd"),
+    ("br", "application/x-brotli"),
+    ("tar", "application/x-tar"),
+    ("7z", "application/x-7z-compressed"),
+    ("rar", "application/vnd.rar"),
+    ("jar", "application/java-archive"),
+    ("apk", "application/vnd.android.package-archive"),
+    ("deb", "application/vnd.debian.binary-package"),
+    ("rpm", "application/x-rpm"),
+    ("dmg", "application/x-apple-diskimage"),
+    ("iso", "application/x-iso9660-image"),
+    ("exe", "application/vnd.microsoft.portable-executable"),
+    ("msi", "application/x-msi"),
+    ("bin", "application/octet-stream"),
+
+    // Office documents
+    ("doc", "application/msword"),
+    ("docx", "application/vnd.openxmlformats-officedocument.wordprocessingml.document"),
+    ("xls", "application/vnd.ms-excel"),
+    ("xlsx", "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet"),
+    ("ppt", "application/vnd.ms-powerpoint"),
+    ("pptx", "application/vnd.openxmlformats-officedocument.presentationml.presentation"),
+    ("odt", "application/vnd.oasis.opendocument.text"),
+    ("ods", "application/vnd.oasis.opendocument.spreadsheet"),
+    ("odp", "application/vnd.oasis.opendocument.presentation"),
+];
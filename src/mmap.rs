@@ -0,0 +1,117 @@
+use std::collections::HashMap;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+
+use iron::response::WriteBody;
+use memmap::Mmap;
+
+use file_id::file_id;
+use requested_path::ResolvedFile;
+
+/// A modifier for `Static` to serve large files from shared, read-only memory maps.
+///
+/// Every file at least `min_size` bytes long is mapped once and the mapping is shared by all
+/// requests for it, so concurrent readers are served straight from the page cache without
+/// per-request buffers. Clones of an `MmapCache` share the same mappings.
+///
+/// A mapping is reused only while the file's identity (device and inode on Unix), length and
+/// modification time match the ones it was created from; a file replaced by a rename gets a
+/// fresh mapping even if tools like `rsync -t` kept its size and modification time, while
+/// responses already in flight keep reading the old one. `Static::watch` drops the mappings of
+/// changed files right away instead of on their next request.
+#[derive(Clone)]
+pub struct MmapCache {
+    min_size: u64,
+    maps: Arc<Mutex<HashMap<PathBuf, Mapping>>>,
+}
+
+struct Mapping {
+    id: Option<(u64, u64)>,
+    len: u64,
+    modified: Option<SystemTime>,
+    map: Arc<Mmap>,
+}
+
+impl MmapCache {
+    /// Create a cache mapping every served file of at least `min_size` bytes.
+    ///
+    /// ## Safety
+    ///
+    /// Reading from a mapping whose file has been truncated raises `SIGBUS` and kills the
+    /// process. By calling this you declare the root immutable: files under it must never be
+    /// truncated or rewritten in place while the handler is running. Replacing files by
+    /// renaming a new version over them is safe.
+    pub unsafe fn immutable_root(min_size: u64) -> MmapCache {
+        MmapCache {
+            // Empty files cannot be mapped.
+            min_size: if min_size == 0 { 1 } else { min_size },
+            maps: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// The number of files currently mapped.
+    pub fn len(&self) -> usize {
+        self.maps.lock().unwrap().len()
+    }
+
+    /// Returns `true` if no files are currently mapped.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Drop the mapping of a single file, if there is one.
+    pub fn invalidate<P: AsRef<Path>>(&self, path: P) {
+        self.maps.lock().unwrap().remove(path.as_ref());
+    }
+
+    /// Drop the mappings of `path` and of every file below it.
+    pub(crate) fn invalidate_under(&self, path: &Path) {
+        self.maps.lock().unwrap().retain(|mapped, _| !mapped.starts_with(path));
+    }
+
+    /// Drop all mappings.
+    pub fn clear(&self) {
+        self.maps.lock().unwrap().clear();
+    }
+
+    /// Returns a body backed by a shared mapping of `file`, and whether the mapping already
+    /// existed, or `None` if the file is too small to be worth mapping.
+    pub(crate) fn body(&self, file: &ResolvedFile) -> io::Result<Option<(MmapBody, bool)>> {
+        let len = file.metadata.len();
+        let handle = match file.file {
+            Some(ref handle) if len >= self.min_size => handle,
+            _ => return Ok(None),
+        };
+        let modified = file.metadata.modified().ok();
+        let id = file_id(&file.metadata);
+
+        let mut maps = self.maps.lock().unwrap();
+
+        if let Some(mapping) = maps.get(&file.path) {
+            if mapping.id == id && mapping.len == len && mapping.modified == modified {
+                return Ok(Some((MmapBody(mapping.map.clone()), true)));
+            }
+        }
+
+        // The caller promised the root is immutable, see `immutable_root`.
+        let map = Arc::new(unsafe { Mmap::map(handle)? });
+        // The file may have changed between the `fstat` and the `mmap`.
+        if map.len() as u64 != len {
+            return Ok(None);
+        }
+
+        maps.insert(file.path.clone(), Mapping { id, len, modified, map: map.clone() });
+        Ok(Some((MmapBody(map), false)))
+    }
+}
+
+/// A response body written from a shared memory map.
+pub struct MmapBody(Arc<Mmap>);
+
+impl WriteBody for MmapBody {
+    fn write_body(&mut self, res: &mut dyn Write) -> io::Result<()> {
+        res.write_all(&self.0)
+    }
+}
@@ -0,0 +1,52 @@
+use std::path::Path;
+
+use glob::{MatchOptions, Pattern, PatternError};
+
+/// A glob matched against the path of a served file relative to the handler's root.
+///
+/// `*` and `?` never match a `/`, `**` matches any number of directories, and a leading `/`
+/// in the pattern is ignored, so `/assets/*.js` and `assets/*.js` are equivalent.
+#[derive(Clone, Debug)]
+pub struct PathPattern(Pattern);
+
+impl PathPattern {
+    pub fn new(pattern: &str) -> Result<PathPattern, PatternError> {
+        Pattern::new(pattern.trim_start_matches('/')).map(PathPattern)
+    }
+
+    /// Like `new`, for builder methods whose patterns are written in the source.
+    ///
+    /// ## Panics
+    ///
+    /// Panics if `pattern` is not a valid glob.
+    pub fn expect(pattern: &str) -> PathPattern {
+        match PathPattern::new(pattern) {
+            Ok(pattern) => pattern,
+            Err(e) => panic!("Invalid glob {:?}: {}", pattern, e),
+        }
+    }
+
+    pub fn matches(&self, relative: &Path) -> bool {
+        self.matches_with(relative, true)
+    }
+
+    /// Like `matches`, but ignoring case where filesystems usually do, so that a rule keeping
+    /// requests away from a file can't be bypassed by changing the case of the URL.
+    pub fn matches_file(&self, relative: &Path) -> bool {
+        self.matches_with(relative, !CASE_INSENSITIVE_FILESYSTEMS)
+    }
+
+    fn matches_with(&self, relative: &Path, case_sensitive: bool) -> bool {
+        self.0.matches_path_with(relative, MatchOptions {
+            case_sensitive,
+            require_literal_separator: true,
+            require_literal_leading_dot: false,
+        })
+    }
+}
+
+/// Whether filesystems on this platform are case-insensitive by default, as APFS, HFS+ and
+/// NTFS are. Case-insensitive directories elsewhere, e.g. with ext4's `casefold`, aren't
+/// detected.
+const CASE_INSENSITIVE_FILESYSTEMS: bool =
+    cfg!(any(target_os = "macos", target_os = "ios", target_os = "windows"));
@@ -0,0 +1,185 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant, SystemTime};
+
+use mount::Mount;
+
+use config::{Config, ConfigError};
+use hot_swap::HotSwap;
+
+/// Serves the mounts of a `Config` file, and rebuilds them when the file is reloaded.
+///
+/// `handler` gives a `HotSwap` to run with Iron. `reload` reads the file again and swaps in
+/// the new mounts, or keeps the current ones and returns the error if the file is invalid;
+/// `reload_on_sighup` and `watch` do the same in the background when the process receives
+/// `SIGHUP` or the file changes, until the `Reloading` they return is dropped. Requests
+/// already being served finish with the settings they started with.
+///
+/// ## Example
+///
+/// ```ignore
+/// let config = ConfigReloader::with_error_handler("staticfile.toml", |path, e| {
+///     eprintln!("keeping the previous configuration, {} is invalid: {}", path.display(), e);
+/// })?;
+/// let _sighup = config.reload_on_sighup()?;
+/// let _watch = config.watch(Duration::from_secs(2))?;
+/// Iron::new(config.handler()).http("localhost:3000")?;
+/// ```
+#[derive(Clone)]
+pub struct ConfigReloader {
+    inner: Arc<Inner>,
+}
+
+struct Inner {
+    path: PathBuf,
+    handler: HotSwap<Mount>,
+    /// The modification time and size of the file when it was last read.
+    stamp: Mutex<Option<(SystemTime, u64)>>,
+    on_error: Box<ErrorHandler>,
+}
+
+type ErrorHandler = dyn Fn(&Path, &ConfigError) + Send + Sync;
+
+impl ConfigReloader {
+    /// Read the configuration at `path`. If the file can't be reloaded in the background, the
+    /// previous configuration is kept without telling anyone.
+    pub fn new<P: Into<PathBuf>>(path: P) -> Result<ConfigReloader, ConfigError> {
+        ConfigReloader::with_error_handler(path, |_, _| {})
+    }
+
+    /// Like `new`, calling `on_error` when the file can't be reloaded in the background.
+    pub fn with_error_handler<P, F>(path: P, on_error: F) -> Result<ConfigReloader, ConfigError>
+        where P: Into<PathBuf>,
+              F: Fn(&Path, &ConfigError) + Send + Sync + 'static
+    {
+        let path = path.into();
+        let stamp = stamp(&path);
+        let config = Config::from_file(&path)?;
+        Ok(ConfigReloader {
+            inner: Arc::new(Inner {
+                path,
+                handler: HotSwap::new(config.mount()),
+                stamp: Mutex::new(stamp),
+                on_error: Box::new(on_error),
+            }),
+        })
+    }
+
+    /// The handler serving the current configuration.
+    pub fn handler(&self) -> HotSwap<Mount> {
+        self.inner.handler.clone()
+    }
+
+    /// Read the file again and serve new requests with its mounts.
+    pub fn reload(&self) -> Result<(), ConfigError> {
+        let stamp = stamp(&self.inner.path);
+        let config = Config::from_file(&self.inner.path)?;
+        self.inner.handler.swap(config.mount());
+        *self.inner.stamp.lock().unwrap_or_else(|poisoned| poisoned.into_inner()) = stamp;
+        Ok(())
+    }
+
+    /// Reload the file whenever the process receives `SIGHUP`, from a background thread that
+    /// runs until the returned `Reloading` is dropped.
+    #[cfg(unix)]
+    pub fn reload_on_sighup(&self) -> io::Result<Reloading> {
+        use signal_hook::consts::SIGHUP;
+        use signal_hook::iterator::Signals;
+
+        // Registering before spawning means no signal is missed once this returns.
+        let mut signals = Signals::new([SIGHUP])?;
+        let handle = signals.handle();
+        let reloader = self.clone();
+        let thread = thread::Builder::new().name("staticfile-sighup".to_owned()).spawn(move || {
+            // Ends once the handle is closed.
+            for _ in signals.forever() {
+                reloader.reload_in_background();
+            }
+        })?;
+        Ok(Reloading { stop: Stop::Signals(handle), thread: Some(thread) })
+    }
+
+    /// Check the file's modification time and size every `interval`, and reload it when either
+    /// changes, from a background thread that runs until the returned `Reloading` is dropped.
+    pub fn watch(&self, interval: Duration) -> io::Result<Reloading> {
+        let stop = Arc::new(AtomicBool::new(false));
+        let stopped = stop.clone();
+        let reloader = self.clone();
+        let thread = thread::Builder::new().name("staticfile-watch".to_owned()).spawn(move || {
+            while sleep_unless_stopped(interval, &stopped) {
+                reloader.reload_if_changed();
+            }
+        })?;
+        Ok(Reloading { stop: Stop::Flag(stop), thread: Some(thread) })
+    }
+
+    fn reload_if_changed(&self) {
+        let current = stamp(&self.inner.path);
+        let changed = {
+            let last = self.inner.stamp.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+            // A file that is missing, e.g. while an editor replaces it, is reloaded once it's back.
+            current.is_some() && current != *last
+        };
+        if changed {
+            self.reload_in_background();
+        }
+    }
+
+    fn reload_in_background(&self) {
+        if let Err(e) = self.reload() {
+            // Don't report the same broken file again on every check.
+            *self.inner.stamp.lock().unwrap_or_else(|poisoned| poisoned.into_inner()) = stamp(&self.inner.path);
+            (self.inner.on_error)(&self.inner.path, &e);
+        }
+    }
+}
+
+/// Reloading started by `ConfigReloader::watch` or `ConfigReloader::reload_on_sighup`, which
+/// stops when this is dropped.
+pub struct Reloading {
+    stop: Stop,
+    thread: Option<JoinHandle<()>>,
+}
+
+enum Stop {
+    Flag(Arc<AtomicBool>),
+    #[cfg(unix)]
+    Signals(signal_hook::iterator::Handle),
+}
+
+impl Drop for Reloading {
+    fn drop(&mut self) {
+        match self.stop {
+            Stop::Flag(ref stop) => stop.store(true, Ordering::SeqCst),
+            #[cfg(unix)]
+            Stop::Signals(ref handle) => handle.close(),
+        }
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+/// How often the watching thread checks whether it should stop.
+const TICK: Duration = Duration::from_millis(50);
+
+/// Wait for `interval`, returning `false` early if `stop` is set.
+fn sleep_unless_stopped(interval: Duration, stop: &AtomicBool) -> bool {
+    let start = Instant::now();
+    while !stop.load(Ordering::SeqCst) {
+        match interval.checked_sub(start.elapsed()) {
+            Some(left) if left > Duration::from_secs(0) => thread::sleep(left.min(TICK)),
+            _ => return true,
+        }
+    }
+    false
+}
+
+fn stamp(path: &Path) -> Option<(SystemTime, u64)> {
+    let metadata = fs::metadata(path).ok()?;
+    Some((metadata.modified().ok()?, metadata.len()))
+}
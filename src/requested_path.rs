@@ -1,7 +1,12 @@
 use iron::Request;
+use iron::headers::ContentLength;
+use iron::modifier::Modifier;
+use iron::Response;
+use file_body::{EmptyBody, FileBody};
 use std::iter::FromIterator;
 use std::path::{Component, PathBuf, Path};
-use std::fs::{self, Metadata};
+use std::fs::{self, File, Metadata};
+use std::io;
 use std::convert::AsRef;
 use url::percent_encoding::percent_decode;
 
@@ -9,9 +14,69 @@ pub struct RequestedPath {
     pub path: PathBuf,
 }
 
+/// A filesystem object resolved from a request.
+///
+/// The metadata is taken from the open handle rather than from a separate `stat` of the path,
+/// so the headers derived from it always describe the bytes that end up in the body.
+pub struct ResolvedFile {
+    pub path: PathBuf,
+    pub metadata: Metadata,
+    /// The open handle. `None` if the file was only `stat`ed because no body will be sent,
+    /// and for directories that cannot be opened on this platform.
+    pub file: Option<File>,
+}
+
+impl ResolvedFile {
+    /// Resolve `path` by opening it if `open` is set, or by reading its metadata otherwise.
+    pub fn new<P: Into<PathBuf>>(path: P, open: bool) -> io::Result<ResolvedFile> {
+        if open {
+            ResolvedFile::open(path)
+        } else {
+            ResolvedFile::stat(path)
+        }
+    }
+
+    pub fn stat<P: Into<PathBuf>>(path: P) -> io::Result<ResolvedFile> {
+        let path = path.into();
+        let metadata = fs::metadata(&path)?;
+        Ok(ResolvedFile { path, metadata, file: None })
+    }
+
+    pub fn open<P: Into<PathBuf>>(path: P) -> io::Result<ResolvedFile> {
+        let path = path.into();
+
+        match File::open(&path) {
+            Ok(file) => {
+                let metadata = file.metadata()?;
+                Ok(ResolvedFile { path, metadata, file: Some(file) })
+            },
+            // Some platforms refuse to open directories as files.
+            Err(e) => match fs::metadata(&path) {
+                Ok(ref metadata) if metadata.is_dir() => {
+                    Ok(ResolvedFile { path, metadata: metadata.clone(), file: None })
+                },
+                _ => Err(e),
+            },
+        }
+    }
+}
+
+impl Modifier<Response> for ResolvedFile {
+    fn modify(self, res: &mut Response) {
+        res.headers.set(ContentLength(self.metadata.len()));
+
+        res.body = match self.file {
+            Some(file) => Some(Box::new(FileBody::new(file))),
+            // Iron would replace the length with zero if there were no body at all.
+            None => Some(Box::new(EmptyBody)),
+        };
+    }
+}
+
+/// The decoded segment, or `None` if it doesn't decode to UTF-8.
 #[inline]
-fn decode_percents(string: &&str) -> String {
-    percent_decode(string.as_bytes()).decode_utf8().unwrap().into_owned()
+fn decode_percents(string: &&str) -> Option<String> {
+    percent_decode(string.as_bytes()).decode_utf8().ok().map(|decoded| decoded.into_owned())
 }
 
 fn normalize_path(path: &Path) -> PathBuf {
@@ -31,11 +96,14 @@ fn normalize_path(path: &Path) -> PathBuf {
 }
 
 impl RequestedPath {
-    pub fn new<P: AsRef<Path>>(root_path: P, request: &Request) -> RequestedPath {
-        let decoded_req_path = PathBuf::from_iter(request.url.path().iter().map(decode_percents));
+    /// Returns `None` if a segment of the path doesn't decode to UTF-8, so it can't name a
+    /// file the handler serves.
+    pub fn new<P: AsRef<Path>>(root_path: P, request: &Request) -> Option<RequestedPath> {
+        let segments: Option<Vec<String>> = request.url.path().iter().map(decode_percents).collect();
+        let decoded_req_path = PathBuf::from_iter(segments?);
         let mut result = root_path.as_ref().to_path_buf();
         result.extend(&normalize_path(&decoded_req_path));
-        RequestedPath { path: result }
+        Some(RequestedPath { path: result })
     }
 
     pub fn should_redirect(&self, metadata: &Metadata, request: &Request) -> bool {
@@ -45,29 +113,18 @@ impl RequestedPath {
         // to URLs like http://example.com
         // Some middleware may mutate the URL's path to violate this property,
         // so the empty list case is handled as a redirect.
-        let has_trailing_slash = match request.url.path().last() {
-            Some(&"") => true,
-            _ => false,
-        };
+        let has_trailing_slash = matches!(request.url.path().last(), Some(&""));
 
         metadata.is_dir() && !has_trailing_slash
     }
 
-    pub fn get_file(self, metadata: &Metadata) -> Option<PathBuf> {
-        if metadata.is_file() {
-            return Some(self.path);
+    pub fn get_file(self, resolved: ResolvedFile, index_files: &[String], open: bool) -> Option<ResolvedFile> {
+        if resolved.metadata.is_file() {
+            return Some(resolved);
         }
 
-        let index_path = self.path.join("index.html");
-
-        match fs::metadata(&index_path) {
-            Ok(m) =>
-                if m.is_file() {
-                    Some(index_path)
-                } else {
-                    None
-                },
-            Err(_) => None,
-        }
+        index_files.iter()
+            .filter_map(|name| ResolvedFile::new(self.path.join(name), open).ok())
+            .find(|index| index.metadata.is_file())
     }
 }
@@ -0,0 +1,40 @@
+use std::fs::Metadata;
+use std::path::PathBuf;
+
+use iron::headers::Encoding;
+use iron::mime::Mime;
+use iron::typemap::Key;
+
+/// The file `Static` answered a request with, for middleware that runs after it.
+///
+/// `Static` inserts it into the extensions of both the request and the response whenever it
+/// serves a file, including the SPA fallback, a `304 Not Modified` or the response to a `HEAD`
+/// request, but not for directory listings, redirects or errors.
+///
+/// ## Example
+///
+/// ```ignore
+/// impl AfterMiddleware for Downloads {
+///     fn after(&self, _: &mut Request, res: Response) -> IronResult<Response> {
+///         if let Some(file) = res.extensions.get::<ServedFile>() {
+///             self.count(&file.path, file.metadata.len());
+///         }
+///         Ok(res)
+///     }
+/// }
+/// ```
+#[derive(Clone, Debug)]
+pub struct ServedFile {
+    /// The path of the file on disk, starting with the handler's root.
+    pub path: PathBuf,
+    /// The metadata of the file when it was served.
+    pub metadata: Metadata,
+    /// The type the file was served as.
+    pub mime: Mime,
+    /// The `Content-Encoding` the body was sent with, if it was compressed.
+    pub encoding: Option<Encoding>,
+}
+
+impl Key for ServedFile {
+    type Value = ServedFile;
+}
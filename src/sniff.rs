@@ -0,0 +1,70 @@
+use std::fs::File;
+use std::io::{self, Read, Seek, SeekFrom};
+use std::str;
+
+use iron::mime::Mime;
+
+/// How much of a file is inspected when guessing its type.
+const SNIFF_LEN: usize = 512;
+
+const SIGNATURES: &[(&[u8], &str)] = &[
+    (b"\x89PNG\r\n\x1a\n", "image/png"),
+    (b"\xff\xd8\xff", "image/jpeg"),
+    (b"GIF87a", "image/gif"),
+    (b"GIF89a", "image/gif"),
+    (b"%PDF-", "application/pdf"),
+    (b"\x1f\x8b", "application/gzip"),
+    (b"PK\x03\x04", "application/zip"),
+    (b"PK\x05\x06", "application/zip"),
+    (b"PK\x07\x08", "application/zip"),
+];
+
+/// Read the first bytes of `file`, leaving its offset at the start so the body is unaffected.
+pub fn read_head(mut file: &File) -> io::Result<Vec<u8>> {
+    let mut head = Vec::with_capacity(SNIFF_LEN);
+    file.take(SNIFF_LEN as u64).read_to_end(&mut head)?;
+    file.seek(SeekFrom::Start(0))?;
+    Ok(head)
+}
+
+/// Guess a type from the first bytes of a file, or `None` if it isn't recognised.
+pub fn sniff(head: &[u8]) -> Option<Mime> {
+    if let Some(&(_, mime)) = SIGNATURES.iter().find(|&&(magic, _)| head.starts_with(magic)) {
+        return mime.parse().ok();
+    }
+
+    if is_text(head) {
+        "text/plain".parse().ok()
+    } else {
+        None
+    }
+}
+
+/// The charset announced by a byte order mark at the start of a file.
+pub fn bom_charset(head: &[u8]) -> Option<&'static str> {
+    if head.starts_with(b"\xef\xbb\xbf") {
+        Some("utf-8")
+    } else if head.starts_with(b"\xfe\xff") {
+        Some("utf-16be")
+    } else if head.starts_with(b"\xff\xfe") {
+        Some("utf-16le")
+    } else {
+        None
+    }
+}
+
+/// Whether `head` looks like the start of a UTF-8 text file.
+fn is_text(head: &[u8]) -> bool {
+    let text = match str::from_utf8(head) {
+        Ok(text) => text,
+        // The head may end in the middle of a character.
+        Err(e) if e.error_len().is_none() && head.len() == SNIFF_LEN => {
+            str::from_utf8(&head[..e.valid_up_to()]).unwrap()
+        },
+        Err(_) => return false,
+    };
+
+    !text.is_empty() && !text.chars().any(|c| {
+        c.is_control() && !matches!(c, '\t' | '\n' | '\r' | '\x0c' | '\x1b')
+    })
+}
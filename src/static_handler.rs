@@ -1,21 +1,51 @@
-use std::path::{PathBuf, Path};
-use std::fs;
+use std::path::{Path, PathBuf};
 use std::error::Error;
 use std::fmt;
+use std::fs::File;
+use std::io::{self, Read};
+use std::sync::Arc;
+use std::time::{Instant, SystemTime};
 
-#[cfg(feature = "cache")]
-use time::{self, Timespec};
 #[cfg(feature = "cache")]
 use std::time::Duration;
+#[cfg(feature = "cache")]
+use cache_policy::CachePolicy;
+#[cfg(feature = "cache")]
+use matcher::{Matcher, MimePattern};
+#[cfg(feature = "cache")]
+use regex::Regex;
 
 use iron::prelude::*;
-use iron::{Handler, Url, status};
-#[cfg(feature = "cache")]
+use iron::{Handler, Headers, Url, status};
+use iron::method::Method;
+use iron::headers::{Allow, CacheControl, CacheDirective, ContentEncoding, ContentLength, ContentType};
+use iron::mime::{Mime, SubLevel, TopLevel};
 use iron::modifier::Modifier;
-use iron::modifiers::Redirect;
+use iron::modifiers::{Header, Redirect, RedirectRaw};
 use mount::OriginalUrl;
-use requested_path::RequestedPath;
+use requested_path::{RequestedPath, ResolvedFile};
+use file_body::EmptyBody;
+use access_log::{self, AccessLog, Served, ServedFrom};
+use authorizer::{Access, Authorizer, Forbidden};
+use metrics::{self, Metrics};
+use served_file::ServedFile;
+use autoindex;
+use live_reload::LiveReload;
+use manifest::{Asset, AssetManifest, OutdatedAssets};
+use mime_types::MimeTypes;
+use validators::Validators;
+use cors::Cors;
+use path_pattern::PathPattern;
+use security_headers::SecurityHeaders;
+use watch::{Targets, Watch, Watcher};
+#[cfg(feature = "gzip")]
+use compression::Compression;
+#[cfg(feature = "mmap")]
+use mmap::MmapCache;
+#[cfg(feature = "auth")]
+use basic_auth::BasicAuth;
 use url;
+use url::percent_encoding::percent_decode;
 
 /// The static file-serving `Handler`.
 ///
@@ -23,7 +53,32 @@ use url;
 /// Incoming requests are mapped onto the filesystem by appending their URL path to the handler's
 /// root path. If the filesystem path corresponds to a regular file, the handler will attempt to
 /// serve it. Otherwise, if the path corresponds to a directory containing an `index.html`,
-/// the handler will attempt to serve that instead.
+/// the handler will attempt to serve that instead. The index files can be changed with
+/// `index_files`, directories without one can be listed with `autoindex`, and paths matching
+/// nothing can be answered with a single page with `spa_fallback`.
+///
+/// The `Content-Type` of served files is chosen by a `MimeTypes` table, which can be
+/// replaced with `set`.
+///
+/// `HEAD` requests get the same headers as a `GET` for the same file, but the file is only
+/// `stat`ed, not opened, unless its content has to be sniffed for its type. `OPTIONS` requests
+/// are answered with the allowed methods, or as CORS preflights if a `Cors` policy is set,
+/// and any other method is refused with `status::MethodNotAllowed` unless a `method_fallback`
+/// handler is set.
+///
+/// Files are sent with `Last-Modified` and `ETag`, and conditional requests for unchanged files
+/// are answered with `status::NotModified`. `Validators` turns either off, and `Cache` adds
+/// `Cache-Control`.
+///
+/// With an `AssetManifest`, files are also served from URLs containing a hash of their content.
+///
+/// Security headers such as `Content-Security-Policy` can be added to served files by setting a
+/// `SecurityHeaders` policy.
+///
+/// For development, `LiveReload` reloads pages in the browser when files change.
+///
+/// A handler's settings can't be changed once it is serving, but it can be wrapped in a
+/// `HotSwap` and replaced with a new one.
 ///
 /// ## Errors
 ///
@@ -34,29 +89,61 @@ use url;
 pub struct Static {
     /// The path this handler is serving files from.
     pub root: PathBuf,
+    mime_types: MimeTypes,
+    validators: Validators,
+    index_files: Vec<String>,
+    autoindex: bool,
+    spa_fallback: Option<PathBuf>,
+    redirects: Vec<(String, String, status::Status)>,
+    denied: Vec<PathPattern>,
+    #[cfg(feature = "auth")]
+    protected: Vec<(PathPattern, BasicAuth)>,
+    authorizer: Option<Arc<dyn Authorizer>>,
+    method_fallback: Option<Arc<dyn Handler>>,
+    access_log: Option<Arc<dyn AccessLog>>,
+    metrics: Option<Arc<dyn AccessLog>>,
+    cors: Option<Cors>,
+    manifest: Option<AssetManifest>,
+    security_headers: Option<SecurityHeaders>,
+    live_reload: Option<LiveReload>,
     #[cfg(feature = "cache")]
     cache: Option<Cache>,
+    #[cfg(feature = "mmap")]
+    mmap: Option<MmapCache>,
+    #[cfg(feature = "gzip")]
+    compression: Option<Compression>,
 }
 
 impl Static {
     /// Create a new instance of `Static` with a given root path.
     ///
     /// If `Path::new("")` is given, files will be served from the current directory.
-    #[cfg(feature = "cache")]
-    pub fn new<P: Into<PathBuf>>(root: P) -> Static {
-        Static {
-            root: root.into(),
-            cache: None
-        }
-    }
-
-    /// Create a new instance of `Static` with a given root path.
-    ///
-    /// If `Path::new("")` is given, files will be served from the current directory.
-    #[cfg(not(feature = "cache"))]
     pub fn new<P: Into<PathBuf>>(root: P) -> Static {
         Static {
             root: root.into(),
+            mime_types: MimeTypes::new(),
+            validators: Validators::new(),
+            index_files: vec!["index.html".to_owned()],
+            autoindex: false,
+            spa_fallback: None,
+            redirects: Vec::new(),
+            denied: Vec::new(),
+            #[cfg(feature = "auth")]
+            protected: Vec::new(),
+            authorizer: None,
+            method_fallback: None,
+            access_log: None,
+            metrics: None,
+            cors: None,
+            manifest: None,
+            security_headers: None,
+            live_reload: None,
+            #[cfg(feature = "cache")]
+            cache: None,
+            #[cfg(feature = "mmap")]
+            mmap: None,
+            #[cfg(feature = "gzip")]
+            compression: None,
         }
     }
 
@@ -73,42 +160,443 @@ impl Static {
         self.set(Cache::new(duration))
     }
 
-    #[cfg(feature = "cache")]
-    fn try_cache<P: AsRef<Path>>(&self, req: &mut Request, path: P) -> IronResult<Response> {
-        match self.cache {
-            None => Ok(Response::with((status::Ok, path.as_ref()))),
-            Some(ref cache) => cache.handle(req, path.as_ref()),
+    /// Pass requests with methods other than `GET`, `HEAD` and `OPTIONS` to `handler` instead
+    /// of refusing them.
+    ///
+    /// ## Example
+    ///
+    /// ```ignore
+    /// let uploads = Static::new(path).method_fallback(upload_handler);
+    /// ```
+    pub fn method_fallback<H: Handler>(mut self, handler: H) -> Static {
+        self.method_fallback = Some(Arc::new(handler));
+        self
+    }
+
+    /// Record every request in `log` once its response has been sent, including requests
+    /// answered with an error.
+    ///
+    /// ## Example
+    ///
+    /// ```ignore
+    /// let logged = Static::new(path).access_log(LogWriter::stdout(LogFormat::Common));
+    /// ```
+    pub fn access_log<L: AccessLog>(mut self, log: L) -> Static {
+        self.access_log = Some(Arc::new(log));
+        self
+    }
+
+    /// Report every request to `metrics` once its response has been sent, including requests
+    /// answered with an error.
+    ///
+    /// ## Example
+    ///
+    /// ```ignore
+    /// let registry = MetricsRegistry::new();
+    /// mount.mount("/", Static::new(path).metrics(registry.clone()));
+    /// mount.mount("/metrics", registry);
+    /// ```
+    pub fn metrics<M: Metrics>(mut self, metrics: M) -> Static {
+        self.metrics = Some(Arc::new(metrics::Recorder(metrics)));
+        self
+    }
+
+    /// Serve the first of `names` that exists in a directory for the directory, instead of
+    /// `index.html`.
+    ///
+    /// ## Example
+    ///
+    /// ```ignore
+    /// let static_handler = Static::new(path).index_files(&["index.html", "index.htm"]);
+    /// ```
+    pub fn index_files<S: AsRef<str>>(mut self, names: &[S]) -> Static {
+        self.index_files = names.iter().map(|name| name.as_ref().to_owned()).collect();
+        self
+    }
+
+    /// List the contents of directories without an index file, instead of answering
+    /// `status::NotFound`.
+    pub fn autoindex(mut self, autoindex: bool) -> Static {
+        self.autoindex = autoindex;
+        self
+    }
+
+    /// Serve the file at `path`, relative to the root, for requests that match no file, as
+    /// single-page applications that route in the browser need.
+    ///
+    /// ## Example
+    ///
+    /// ```ignore
+    /// let app = Static::new(path).spa_fallback("index.html");
+    /// ```
+    pub fn spa_fallback<P: Into<PathBuf>>(mut self, path: P) -> Static {
+        self.spa_fallback = Some(path.into());
+        self
+    }
+
+    /// Redirect requests for `from`, a path relative to where the handler is mounted, to the
+    /// URL or absolute path `to` with `status`, e.g. `status::MovedPermanently`.
+    ///
+    /// ## Example
+    ///
+    /// ```ignore
+    /// let site = Static::new(path).redirect("/old.html", "/new.html", status::MovedPermanently);
+    /// ```
+    pub fn redirect(mut self, from: &str, to: &str, status: status::Status) -> Static {
+        self.redirects.push((from.trim_matches('/').to_owned(), to.to_owned(), status));
+        self
+    }
+
+    /// Answer `status::NotFound` for files whose path relative to the root matches `pattern`,
+    /// and leave them out of directory listings. Like the patterns of `basic_auth`, `pattern`
+    /// ignores case on macOS and Windows.
+    ///
+    /// ## Panics
+    ///
+    /// Panics if `pattern` is not a valid glob.
+    pub fn deny(mut self, pattern: &str) -> Static {
+        self.denied.push(PathPattern::expect(pattern));
+        self
+    }
+
+    /// Require the credentials of one of `auth`'s users for files whose path relative to the
+    /// root matches `pattern`, and for everything below directories it matches. Without them,
+    /// requests are answered with `status::Unauthorized`, and directory listings leave the
+    /// files out.
+    ///
+    /// On macOS and Windows, whose filesystems ignore case, `pattern` does too, so that
+    /// `/docs/INTERNAL/plans.txt` is protected by `**/internal`. Elsewhere it is matched with
+    /// case, so directories made case-insensitive there, e.g. with ext4's `casefold`, should
+    /// not be protected.
+    ///
+    /// ## Example
+    ///
+    /// ```ignore
+    /// let users = Htpasswd::open("/etc/staticfile/htpasswd")?;
+    /// let site = Static::new(path).basic_auth("**/internal", BasicAuth::new("Staff only", users));
+    /// ```
+    ///
+    /// ## Panics
+    ///
+    /// Panics if `pattern` is not a valid glob.
+    #[cfg(feature = "auth")]
+    pub fn basic_auth(mut self, pattern: &str, auth: BasicAuth) -> Static {
+        self.protected.push((PathPattern::expect(pattern), auth));
+        self
+    }
+
+    /// Let `authorizer` decide which files each request may open, by their normalized path
+    /// relative to the root, after any `basic_auth` credentials have been checked.
+    ///
+    /// ## Example
+    ///
+    /// ```ignore
+    /// let site = Static::new(path).authorizer(|req: &Request, path: &Path| {
+    ///     if path.starts_with("members") && !signed_in(req) { Access::Deny } else { Access::Allow }
+    /// });
+    /// ```
+    pub fn authorizer<A: Authorizer>(mut self, authorizer: A) -> Static {
+        self.authorizer = Some(Arc::new(authorizer));
+        self
+    }
+
+    /// Watch the root in the background, and drop the state kept for files as they change,
+    /// until the returned `Watcher` is dropped. Call this once the handler is configured.
+    ///
+    /// ## Example
+    ///
+    /// ```ignore
+    /// let static_handler = Static::new(path).set(AssetManifest::build(path)?);
+    /// let _watcher = static_handler.watch(Watch::new())?;
+    /// ```
+    pub fn watch(&self, watch: Watch) -> io::Result<Watcher> {
+        let targets = Targets {
+            manifest: self.manifest.clone(),
+            #[cfg(feature = "mmap")]
+            mmap: self.mmap.clone(),
+        };
+        Watcher::start(&self.root, targets, watch)
+    }
+
+    fn is_denied(&self, path: &Path) -> bool {
+        let relative = self.relative(path);
+        self.denied.iter().any(|pattern| pattern.matches_file(relative))
+    }
+
+    /// The response refusing `req` if it may not open `path`, which must be normalized.
+    fn check_access(&self, req: &mut Request, path: &Path) -> Option<IronResult<Response>> {
+        let relative = self.relative(path);
+        #[cfg(feature = "auth")]
+        {
+            for (pattern, auth) in &self.protected {
+                let protects = relative.ancestors().any(|ancestor| {
+                    !ancestor.as_os_str().is_empty() && pattern.matches_file(ancestor)
+                });
+                if protects && !auth.admits(req) {
+                    return Some(Err(auth.challenge()));
+                }
+            }
+        }
+        match self.authorizer.as_ref().map(|authorizer| authorizer.authorize(req, relative)) {
+            None | Some(Access::Allow) => None,
+            Some(Access::Deny) => Some(Err(IronError::new(Forbidden, status::Forbidden))),
+            Some(Access::Redirect(to)) => Some(Ok(Response::with((status::Found,
+                                                                  format!("Redirecting to {}", to),
+                                                                  RedirectRaw(to))))),
         }
     }
-}
 
-impl Handler for Static {
-    fn handle(&self, req: &mut Request) -> IronResult<Response> {
-        use std::io;
+    /// The path of a file below the root, as matched by globs.
+    fn relative<'a>(&self, path: &'a Path) -> &'a Path {
+        path.strip_prefix(&self.root).unwrap_or(path)
+    }
+
+    fn serve(&self, req: &mut Request, file: ResolvedFile) -> IronResult<Response> {
+        // The index file of a directory or the fallback may be protected when the URL isn't.
+        if let Some(refusal) = self.check_access(req, &file.path) {
+            return refusal;
+        }
+        let mime = self.mime_types.mime_for(&file, self.relative(&file.path));
+
+        #[cfg(feature = "mmap")]
+        let mapped = match self.mmap {
+            Some(ref mmap) => mmap.body(&file)
+                .map_err(|e| IronError::new(e, status::InternalServerError))?,
+            None => None,
+        };
 
-        let requested_path = RequestedPath::new(&self.root, req);
+        // A page to inject the live reload script into is read from the handle it was served
+        // from, which the response takes along.
+        let live_reloads = self.live_reload.is_some()
+            && matches!(mime, Mime(TopLevel::Text, SubLevel::Html, _));
+        let handle = match file.file {
+            Some(ref handle) if live_reloads => Some(handle.try_clone().map_err(io_error)?),
+            _ => None,
+        };
 
-        let metadata = match fs::metadata(&requested_path.path) {
-            Ok(meta) => meta,
-            Err(e) => {
-                let status = match e.kind() {
-                    io::ErrorKind::NotFound => status::NotFound,
-                    io::ErrorKind::PermissionDenied => status::Forbidden,
-                    _ => status::InternalServerError,
+        let (path, metadata) = (file.path.clone(), file.metadata.clone());
+        let relative = self.relative(&path).to_path_buf();
+        let mut response = self.validators.respond(req, file);
+        self.apply_cache(&relative, &mime, &mut response);
+
+        // GET and HEAD must agree on the type, so it is never left to the response builders.
+        if response.status == Some(status::Ok) {
+            response.headers.set(ContentType(mime.clone()));
+        }
+        if self.mime_types.sniffs() {
+            response.headers.set_raw("X-Content-Type-Options", vec![b"nosniff".to_vec()]);
+        }
+        if let Some(ref security_headers) = self.security_headers {
+            if matches!(response.status, Some(status::Ok) | Some(status::PartialContent) | Some(status::NotModified)) {
+                security_headers.apply(&relative, &mime, &mut response.headers);
+            }
+        }
+
+        // Only swap the body of responses that actually carry the file.
+        #[cfg(feature = "mmap")]
+        let cache_hit = match mapped {
+            Some((body, hit)) if response.body.is_some() => {
+                response.body = Some(Box::new(body));
+                Some(hit)
+            },
+            _ => None,
+        };
+        #[cfg(not(feature = "mmap"))]
+        let cache_hit = None;
+
+        let injected = match self.live_reload {
+            Some(ref live_reload) if live_reloads && response.status == Some(status::Ok) => {
+                // HEAD requests only stat the file, so it is opened for the length.
+                let mut handle = match handle {
+                    Some(handle) => handle,
+                    None => File::open(&path).map_err(io_error)?,
                 };
+                let mut page = Vec::with_capacity(metadata.len() as usize);
+                handle.read_to_end(&mut page).map_err(io_error)?;
+                let page = live_reload.inject(req, page);
+                response.headers.set(ContentLength(page.len() as u64));
+                if req.method != Method::Head {
+                    response.body = Some(Box::new(page));
+                }
+                true
+            },
+            _ => false,
+        };
+        let length = match response.body {
+            Some(_) => response.headers.get::<ContentLength>().map(|&ContentLength(len)| len),
+            None => None,
+        };
+        response.extensions.insert::<Served>(ServedFrom {
+            length,
+            cache_hit: if injected { None } else { cache_hit },
+        });
+
+        #[cfg(feature = "gzip")]
+        {
+            if let Some(ref compression) = self.compression {
+                compression.apply(req, &mime, &mut response);
+            }
+        }
+
+        let encoding = response.headers.get::<ContentEncoding>()
+            .and_then(|encodings| encodings.last().cloned());
+        let served = ServedFile { path, metadata, mime, encoding };
+        req.extensions.insert::<ServedFile>(served.clone());
+        response.extensions.insert::<ServedFile>(served);
+
+        Ok(response)
+    }
 
-                return Err(IronError::new(e, status))
+    #[cfg(feature = "cache")]
+    fn apply_cache(&self, relative: &Path, mime: &Mime, response: &mut Response) {
+        if let Some(ref cache) = self.cache {
+            // A 304 refreshes the client's copy, so it repeats the policy.
+            if matches!(response.status, Some(status::Ok) | Some(status::NotModified)) {
+                cache.policy(relative, mime).apply(&mut response.headers);
+            }
+        }
+    }
+
+    #[cfg(not(feature = "cache"))]
+    fn apply_cache(&self, _: &Path, _: &Mime, _: &mut Response) {}
+
+    fn serve_fallback(&self, req: &mut Request, open: bool) -> IronResult<Response> {
+        let path = self.root.join(self.spa_fallback.as_ref().unwrap());
+        let file = ResolvedFile::new(path, open).map_err(io_error)?;
+
+        if file.metadata.is_file() {
+            self.serve(req, file)
+        } else {
+            Err(IronError::new(NoFile, status::NotFound))
+        }
+    }
+
+    fn list(&self, req: &mut Request, dir: &Path) -> IronResult<Response> {
+        let url_path = format!("/{}", original_url(req).path().trim_start_matches('/'));
+        let url_path = percent_decode(url_path.as_bytes()).decode_utf8_lossy().into_owned();
+        let html = autoindex::listing(dir, &url_path, |path| {
+            self.is_denied(path) || self.check_access(req, path).is_some()
+        }).map_err(io_error)?;
+        let html = match self.live_reload {
+            Some(ref live_reload) => live_reload.inject(req, html.into_bytes()),
+            None => html.into_bytes(),
+        };
+
+        let mut response = Response::with((status::Ok,
+                                           Header(ContentType::html()),
+                                           Header(ContentLength(html.len() as u64))));
+        response.body = if req.method == Method::Head {
+            // Iron would replace the length with zero if there were no body at all.
+            Some(Box::new(EmptyBody))
+        } else {
+            Some(Box::new(html))
+        };
+
+        // Listings are matched by the path of their directory, as an HTML document.
+        let (relative, mime) = (self.relative(dir), Mime(TopLevel::Text, SubLevel::Html, vec![]));
+        self.apply_cache(relative, &mime, &mut response);
+        if let Some(ref security_headers) = self.security_headers {
+            security_headers.apply(relative, &mime, &mut response.headers);
+        }
+
+        Ok(response)
+    }
+
+    fn respond(&self, req: &mut Request) -> IronResult<Response> {
+        match req.method {
+            Method::Get | Method::Head => {},
+            Method::Options => {
+                let preflight = self.cors.as_ref().and_then(|cors| cors.preflight(req, ALLOWED_METHODS));
+                return Ok(preflight.unwrap_or_else(|| {
+                    Response::with((status::Ok, Header(Allow(allowed_methods()))))
+                }));
+            },
+            _ => return match self.method_fallback {
+                Some(ref handler) => handler.handle(req),
+                None => Err(IronError::new(MethodNotAllowed,
+                                           (status::MethodNotAllowed, Header(Allow(allowed_methods()))))),
+            },
+        }
+
+        if let Some(ref live_reload) = self.live_reload {
+            if live_reload.is_endpoint(req) {
+                return live_reload.events(req, |watch| self.watch(watch));
+            }
+        }
+
+        let mut requested_path = match RequestedPath::new(&self.root, req) {
+            Some(requested_path) => requested_path,
+            None => return Err(IronError::new(NoFile, status::NotFound)),
+        };
+        let mut hashed = false;
+
+        if !self.redirects.is_empty() {
+            let relative = self.relative(&requested_path.path).to_string_lossy().replace('\\', "/");
+            if let Some((_, to, status)) = self.redirects.iter().find(|(from, _, _)| *from == relative) {
+                return Ok(Response::with((*status,
+                                          format!("Redirecting to {}", to),
+                                          RedirectRaw(to.clone()))));
+            }
+        }
+
+        let mut outdated = None;
+        if let Some(ref manifest) = self.manifest {
+            let relative = self.relative(&requested_path.path).to_path_buf();
+            match manifest.lookup(&relative).map_err(|e| IronError::new(e, status::InternalServerError))? {
+                Some(Asset::Current(original)) => {
+                    requested_path.path = self.root.join(original);
+                    hashed = true;
+                },
+                Some(Asset::Outdated(original, current)) => {
+                    // Access is decided for the original, so the current hash isn't given away.
+                    requested_path.path = self.root.join(original);
+                    outdated = Some((manifest.outdated_assets(), current));
+                },
+                None => {},
+            }
+        }
+
+        if self.is_denied(&requested_path.path) {
+            return Err(IronError::new(NoFile, status::NotFound));
+        }
+        // Before the file is looked up, so that whether a protected file exists isn't given away.
+        if let Some(refusal) = self.check_access(req, &requested_path.path) {
+            return refusal;
+        }
+
+        if let Some((outdated_assets, current)) = outdated {
+            return match outdated_assets {
+                OutdatedAssets::NotFound => Err(IronError::new(NoFile, status::NotFound)),
+                OutdatedAssets::Redirect => {
+                    // Only the file name changes with the hash.
+                    let name = current.rsplit('/').next().unwrap_or(&current);
+                    let mut current_url = original_url(req);
+                    current_url.path_segments_mut().unwrap().pop().push(name);
+                    let redirect_path = Url::from_generic_url(current_url).unwrap();
+
+                    Ok(Response::with((status::Found,
+                                       format!("Redirecting to {}", redirect_path),
+                                       Redirect(redirect_path))))
+                },
+            };
+        }
+
+        // A HEAD response has no body, so there is no need to open the file.
+        let open = req.method != Method::Head;
+
+        let resolved = match ResolvedFile::new(&requested_path.path, open) {
+            Ok(resolved) => resolved,
+            Err(ref e) if e.kind() == io::ErrorKind::NotFound && self.spa_fallback.is_some() => {
+                return self.serve_fallback(req, open);
             },
+            Err(e) => return Err(io_error(e)),
         };
 
         // If the URL ends in a slash, serve the file directly.
         // Otherwise, redirect to the directory equivalent of the URL.
-        if requested_path.should_redirect(&metadata, req) {
+        if requested_path.should_redirect(&resolved.metadata, req) {
             // Perform an HTTP 301 Redirect.
-            let mut original_url: url::Url = match req.extensions.get::<OriginalUrl>() {
-                None => &req.url,
-                Some(original_url) => original_url,
-            }.clone().into();
+            let mut original_url = original_url(req);
 
             // Append the trailing slash
             //
@@ -122,98 +610,242 @@ impl Handler for Static {
                                       Redirect(redirect_path))));
         }
 
-        match requested_path.get_file(&metadata) {
-            // If no file is found, return a 404 response.
-            None => Err(IronError::new(NoFile, status::NotFound)),
-            // Won't panic because we know the file exists from get_file.
-            #[cfg(feature = "cache")]
-            Some(path) => self.try_cache(req, path),
-            #[cfg(not(feature = "cache"))]
-            Some(path) => {
-                let path: &Path = &path;
-                Ok(Response::with((status::Ok, path)))
+        let dir = if resolved.metadata.is_dir() { Some(requested_path.path.clone()) } else { None };
+
+        match requested_path.get_file(resolved, &self.index_files, open) {
+            None => match dir {
+                Some(ref dir) if self.autoindex => self.list(req, dir),
+                _ if self.spa_fallback.is_some() => self.serve_fallback(req, open),
+                // If no file is found, return a 404 response.
+                _ => Err(IronError::new(NoFile, status::NotFound)),
+            },
+            // The file is already open, so serving it cannot race with changes on disk.
+            Some(file) => {
+                let mut response = self.serve(req, file)?;
+                // A hashed URL always names the same content.
+                if hashed && matches!(response.status, Some(status::Ok) | Some(status::NotModified)) {
+                    make_immutable(&mut response.headers);
+                }
+                Ok(response)
             },
         }
     }
 }
 
+/// Let a response be cached for as long as possible, while keeping who may store it, e.g.
+/// `private` or `no-store` from a `Cache` rule.
+fn make_immutable(headers: &mut Headers) {
+    let mut directives = headers.get::<CacheControl>()
+        .map_or_else(Vec::new, |cache_control| cache_control.0.clone());
+    directives.retain(|directive| !matches!(*directive, CacheDirective::MaxAge(_)));
+    let restricted = directives.iter()
+        .any(|directive| matches!(*directive, CacheDirective::Private | CacheDirective::NoStore));
+    if !restricted && !directives.contains(&CacheDirective::Public) {
+        directives.insert(0, CacheDirective::Public);
+    }
+    directives.push(CacheDirective::MaxAge(IMMUTABLE_MAX_AGE as u32));
+    if !directives.iter().any(|directive| directive.to_string() == "immutable") {
+        directives.push(CacheDirective::Extension("immutable".to_owned(), None));
+    }
+    headers.set(CacheControl(directives));
+}
+
+/// The status for an error opening a file.
+fn io_error(e: io::Error) -> IronError {
+    let status = match e.kind() {
+        io::ErrorKind::NotFound => status::NotFound,
+        io::ErrorKind::PermissionDenied => status::Forbidden,
+        _ => status::InternalServerError,
+    };
+
+    IronError::new(e, status)
+}
+
+/// The URL of a request before `Mount` stripped its prefix.
+fn original_url(req: &Request) -> url::Url {
+    match req.extensions.get::<OriginalUrl>() {
+        None => &req.url,
+        Some(original_url) => original_url,
+    }.clone().into()
+}
+
+impl Handler for Static {
+    fn handle(&self, req: &mut Request) -> IronResult<Response> {
+        let (started, time) = (Instant::now(), SystemTime::now());
+        let mut result = self.respond(req);
+
+        if let Some(ref cors) = self.cors {
+            match result {
+                Ok(ref mut response) => cors.apply(req, &mut response.headers),
+                Err(ref mut error) => cors.apply(req, &mut error.response.headers),
+            }
+        }
+
+        let logs: Vec<_> = self.access_log.iter().chain(&self.metrics).cloned().collect();
+        if !logs.is_empty() {
+            match result {
+                Ok(ref mut response) => access_log::attach(logs, req, started, time, response),
+                Err(ref mut error) => access_log::attach(logs, req, started, time, &mut error.response),
+            }
+        }
+
+        result
+    }
+}
+
 impl Set for Static {}
 
 /// A modifier for `Static` to specify a response's `cache-control`.
+///
+/// This only governs how long copies stay fresh. The validators used to revalidate them are
+/// sent whether or not a `Cache` is set, as chosen by `Validators`.
+///
+/// Files are sent with `public, max-age` of `duration` unless one of the rules added with
+/// `glob` or `mime` applies to them. Rules are tried in the order they were added and the
+/// first match decides the `CachePolicy`. `304 Not Modified` responses repeat the policy.
+/// Directory listings get the policy of their directory's path and `text/html`.
+///
+/// With `fingerprinted`, files that no rule applies to and whose names contain a content hash,
+/// such as `main.3f9a2c1b.js`, are sent with `public, max-age=31536000, immutable`, since a
+/// new build gives changed content a new name.
+///
+/// ## Example
+///
+/// ```ignore
+/// let cache = Cache::new(Duration::from_secs(60 * 60))
+///     .mime("text/html", CachePolicy::new().no_cache())
+///     .glob("/private/**", CachePolicy::new().private().no_store())
+///     .fingerprinted();
+/// let static_handler = Static::new(path).set(cache);
+/// ```
 #[cfg(feature = "cache")]
 #[derive(Clone)]
 pub struct Cache {
     /// The length of time the file should be cached for.
     pub duration: Duration,
+    rules: Vec<(Matcher, CachePolicy)>,
+    fingerprint: Option<Regex>,
 }
 
 #[cfg(feature = "cache")]
 impl Cache {
     /// Create a new instance of `Cache` with a given duration.
     pub fn new(duration: Duration) -> Cache {
-        Cache { duration: duration }
+        Cache { duration, rules: Vec::new(), fingerprint: None }
     }
 
-    fn handle<P: AsRef<Path>>(&self, req: &mut Request, path: P) -> IronResult<Response> {
-        use iron::headers::{IfModifiedSince, HttpDate};
+    /// Send files whose path relative to the root matches `pattern` with `policy`.
+    ///
+    /// ## Panics
+    ///
+    /// Panics if `pattern` is not a valid glob.
+    pub fn glob(mut self, pattern: &str, policy: CachePolicy) -> Cache {
+        self.rules.push((Matcher::Glob(PathPattern::expect(pattern)), policy));
+        self
+    }
 
-        let path = path.as_ref();
+    /// Send files served with a type matching `pattern`, such as `text/html` or `image/*`,
+    /// with `policy`.
+    ///
+    /// ## Panics
+    ///
+    /// Panics if `pattern` is not of the form `type/subtype`.
+    pub fn mime(mut self, pattern: &str, policy: CachePolicy) -> Cache {
+        self.rules.push((Matcher::Mime(MimePattern::expect(pattern)), policy));
+        self
+    }
 
-        let (size, last_modified_time) = match fs::metadata(path) {
-            Err(error) => return Err(IronError::new(error, status::InternalServerError)),
-            Ok(metadata) => {
-                use filetime::FileTime;
+    /// Send files whose names contain a hex content hash of at least 8 digits, set off by `.`
+    /// or `-` before the extension (`main.3f9a2c1b.js`, `app-3f9a2c1b.min.css`), as immutable.
+    pub fn fingerprinted(self) -> Cache {
+        self.fingerprint_pattern(FINGERPRINT)
+    }
 
-                let time = FileTime::from_last_modification_time(&metadata);
-                (metadata.len(), Timespec::new(time.seconds() as i64, 0))
-            },
-        };
+    /// Like `fingerprinted`, recognising fingerprints with a regular expression matched against
+    /// the path relative to the root, e.g. `r"-[A-Za-z0-9_-]{8}\.js$"` for hashes that aren't hex.
+    ///
+    /// ## Panics
+    ///
+    /// Panics if `pattern` is not a valid regular expression.
+    pub fn fingerprint_pattern(mut self, pattern: &str) -> Cache {
+        match Regex::new(pattern) {
+            Ok(regex) => self.fingerprint = Some(regex),
+            Err(e) => panic!("Invalid fingerprint pattern {:?}: {}", pattern, e),
+        }
+        self
+    }
 
-        let if_modified_since = match req.headers.get::<IfModifiedSince>().cloned() {
-            None => return self.response_with_cache(req, path, size, last_modified_time),
-            Some(IfModifiedSince(HttpDate(time))) => time.to_timespec(),
-        };
+    fn policy(&self, relative: &Path, mime: &Mime) -> CachePolicy {
+        if let Some((_, policy)) = self.rules.iter().find(|(matcher, _)| matcher.matches(relative, mime)) {
+            return policy.clone();
+        }
 
-        if last_modified_time <= if_modified_since {
-            Ok(Response::with(status::NotModified))
+        let fingerprinted = match (&self.fingerprint, relative.to_str()) {
+            (Some(regex), Some(relative)) => regex.is_match(relative),
+            _ => false,
+        };
+        if fingerprinted {
+            CachePolicy::new().public().max_age(Duration::from_secs(IMMUTABLE_MAX_AGE)).immutable()
         } else {
-            self.response_with_cache(req, path, size, last_modified_time)
+            CachePolicy::new().public().max_age(self.duration)
         }
     }
+}
 
-    fn response_with_cache<P: AsRef<Path>>(&self,
-                                           req: &mut Request,
-                                           path: P,
-                                           size: u64,
-                                           modified: Timespec) -> IronResult<Response> {
-        use iron::headers::{CacheControl, LastModified, CacheDirective, HttpDate};
-        use iron::headers::{ContentLength, ContentType, ETag, EntityTag};
-        use iron::method::Method;
-        use iron::mime::{Mime, TopLevel, SubLevel};
-        use iron::modifiers::Header;
+/// A hex hash of at least 8 digits in the file name, before the extension.
+#[cfg(feature = "cache")]
+const FINGERPRINT: &str = r"[.-][0-9a-fA-F]{8,}\.[^/]+$";
 
-        let seconds = self.duration.as_secs() as u32;
-        let cache = vec![CacheDirective::Public, CacheDirective::MaxAge(seconds)];
-        let metadata = fs::metadata(path.as_ref());
+/// A year, the longest `max-age` that caches are expected to honour.
+const IMMUTABLE_MAX_AGE: u64 = 365 * 24 * 60 * 60;
 
-        let metadata = try!(metadata.map_err(|e| IronError::new(e, status::InternalServerError)));
+impl Modifier<Static> for Cors {
+    fn modify(self, static_handler: &mut Static) {
+        static_handler.cors = Some(self);
+    }
+}
 
-        let mut response = if req.method == Method::Head {
-            let has_ct = req.headers.get::<ContentType>();
-            let cont_type = match has_ct {
-                None => ContentType(Mime(TopLevel::Text, SubLevel::Plain, vec![])),
-                Some(t) => t.clone()
-            };
-            Response::with((status::Ok, Header(cont_type), Header(ContentLength(metadata.len()))))
-        } else {
-            Response::with((status::Ok, path.as_ref()))
-        };
+impl Modifier<Static> for SecurityHeaders {
+    fn modify(self, static_handler: &mut Static) {
+        static_handler.security_headers = Some(self);
+    }
+}
+
+impl Modifier<Static> for LiveReload {
+    fn modify(self, static_handler: &mut Static) {
+        static_handler.live_reload = Some(self);
+    }
+}
 
-        response.headers.set(CacheControl(cache));
-        response.headers.set(LastModified(HttpDate(time::at(modified))));
-        response.headers.set(ETag(EntityTag::weak(format!("{0:x}-{1:x}.{2:x}", size, modified.sec, modified.nsec))));
+impl Modifier<Static> for AssetManifest {
+    fn modify(self, static_handler: &mut Static) {
+        static_handler.manifest = Some(self);
+    }
+}
 
-        Ok(response)
+impl Modifier<Static> for Validators {
+    fn modify(self, static_handler: &mut Static) {
+        static_handler.validators = self;
+    }
+}
+
+impl Modifier<Static> for MimeTypes {
+    fn modify(self, static_handler: &mut Static) {
+        static_handler.mime_types = self;
+    }
+}
+
+#[cfg(feature = "gzip")]
+impl Modifier<Static> for Compression {
+    fn modify(self, static_handler: &mut Static) {
+        static_handler.compression = Some(self);
+    }
+}
+
+#[cfg(feature = "mmap")]
+impl Modifier<Static> for MmapCache {
+    fn modify(self, static_handler: &mut Static) {
+        static_handler.mmap = Some(self);
     }
 }
 
@@ -224,6 +856,12 @@ impl Modifier<Static> for Cache {
     }
 }
 
+const ALLOWED_METHODS: &str = "GET, HEAD, OPTIONS";
+
+fn allowed_methods() -> Vec<Method> {
+    vec![Method::Get, Method::Head, Method::Options]
+}
+
 /// Thrown if no file is found. It is always accompanied by a NotFound response.
 #[derive(Debug)]
 pub struct NoFile;
@@ -234,6 +872,21 @@ impl Error for NoFile {
 
 impl fmt::Display for NoFile {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        f.write_str(self.description())
+        f.write_str("File not found")
+    }
+}
+
+/// Thrown if a request's method is not served. It is always accompanied by a MethodNotAllowed
+/// response listing the allowed methods.
+#[derive(Debug)]
+pub struct MethodNotAllowed;
+
+impl Error for MethodNotAllowed {
+    fn description(&self) -> &str { "Method not allowed" }
+}
+
+impl fmt::Display for MethodNotAllowed {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("Method not allowed")
     }
 }
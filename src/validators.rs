@@ -0,0 +1,120 @@
+use std::time::UNIX_EPOCH;
+
+use iron::prelude::*;
+use iron::status;
+use iron::headers::{Date, ETag, EntityTag, HttpDate, IfModifiedSince, IfNoneMatch, LastModified};
+use time::{self, Timespec};
+
+use requested_path::ResolvedFile;
+
+/// A modifier for `Static` to choose the validators sent with files, which let clients
+/// revalidate a cached copy with a conditional request.
+///
+/// `Last-Modified` and a weak `ETag` built from the size and modification time are both sent
+/// unless turned off, and requests whose `If-None-Match` or `If-Modified-Since` shows that the
+/// client's copy is current are answered with `304 Not Modified`. How long a copy stays fresh
+/// without revalidation is up to `Cache`.
+///
+/// `Last-Modified` is never later than the response's `Date`, even for files with a
+/// modification time in the future, and `If-Modified-Since` dates later than `Date` are
+/// ignored as required by RFC 7232.
+///
+/// ## Example
+///
+/// ```ignore
+/// let static_handler = Static::new(path).set(Validators::new().last_modified(false));
+/// ```
+#[derive(Clone, Copy, Debug)]
+pub struct Validators {
+    last_modified: bool,
+    etag: bool,
+}
+
+impl Validators {
+    /// Send both `Last-Modified` and `ETag`.
+    pub fn new() -> Validators {
+        Validators { last_modified: true, etag: true }
+    }
+
+    /// Send no validators and answer every request with the whole file.
+    pub fn none() -> Validators {
+        Validators { last_modified: false, etag: false }
+    }
+
+    /// Send `Last-Modified` and honour `If-Modified-Since`.
+    pub fn last_modified(mut self, last_modified: bool) -> Validators {
+        self.last_modified = last_modified;
+        self
+    }
+
+    /// Send `ETag` and honour `If-None-Match`.
+    pub fn etag(mut self, etag: bool) -> Validators {
+        self.etag = etag;
+        self
+    }
+
+    /// Respond with `file`, or with `status::NotModified` if the client's copy is current.
+    pub(crate) fn respond(&self, req: &Request, file: ResolvedFile) -> Response {
+        if !self.last_modified && !self.etag {
+            return Response::with((status::Ok, file));
+        }
+
+        let modified = file.metadata.modified().ok()
+            .and_then(|modified| modified.duration_since(UNIX_EPOCH).ok())
+            .map(|since_epoch| Timespec::new(since_epoch.as_secs() as i64, since_epoch.subsec_nanos() as i32));
+
+        // Dates in headers have whole seconds, and a file from the future, e.g. unpacked from
+        // an archive made on a machine with a fast clock, must not claim to be newer than the
+        // response itself.
+        let now = Timespec::new(time::get_time().sec, 0);
+        let last_modified = modified
+            .filter(|_| self.last_modified)
+            .map(|modified| Timespec::new(modified.sec.min(now.sec), 0));
+        // The ETag keeps the full precision of the modification time, so that files changed
+        // twice within a second are told apart.
+        let etag = modified
+            .filter(|_| self.etag)
+            .map(|modified| EntityTag::weak(format!("{0:x}-{1:x}.{2:x}",
+                                                    file.metadata.len(), modified.sec, modified.nsec)));
+
+        let not_modified = match req.headers.get::<IfNoneMatch>() {
+            // `If-None-Match` takes precedence over `If-Modified-Since` (RFC 7232, 6).
+            Some(IfNoneMatch::Any) => true,
+            Some(IfNoneMatch::Items(tags)) => match etag {
+                Some(ref etag) => tags.iter().any(|tag| tag.weak_eq(etag)),
+                None => false,
+            },
+            None => match (last_modified, req.headers.get::<IfModifiedSince>()) {
+                // A date later than the response's is invalid and must be ignored (RFC 7232, 3.3).
+                (Some(last_modified), Some(&IfModifiedSince(HttpDate(since)))) => {
+                    let since = since.to_timespec();
+                    since <= now && last_modified <= since
+                },
+                _ => false,
+            },
+        };
+
+        let mut response = if not_modified {
+            Response::with(status::NotModified)
+        } else {
+            let mut response = Response::with((status::Ok, file));
+            if let Some(last_modified) = last_modified {
+                response.headers.set(LastModified(HttpDate(time::at_utc(last_modified))));
+            }
+            response
+        };
+
+        response.headers.set(Date(HttpDate(time::at_utc(now))));
+        if let Some(etag) = etag {
+            response.headers.set(ETag(etag));
+        }
+
+        response
+    }
+}
+
+impl Default for Validators {
+    fn default() -> Validators {
+        Validators::new()
+    }
+}
@@ -0,0 +1,456 @@
+use std::collections::{BTreeSet, HashMap};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant, SystemTime};
+
+use file_id::Visited;
+use manifest::AssetManifest;
+#[cfg(feature = "mmap")]
+use mmap::MmapCache;
+
+/// Options for `Static::watch`, which watches a handler's root and drops the state it keeps
+/// for files that change: the hashes of its `AssetManifest` and the mappings of its
+/// `MmapCache`. `ETag`s and `Last-Modified` are read from each file when it is served, so
+/// they never go stale.
+///
+/// Changes are noticed with inotify when the `watch` feature is enabled on Linux, and by
+/// scanning the root every `poll_interval` otherwise. Since inotify doesn't see changes made
+/// by other machines, scanning is also used for roots on NFS, SMB and FUSE filesystems, such
+/// as the bind mounts of containers on a VM; `polling` forces it for other cases.
+///
+/// Changes are handled once none have been seen for the `debounce` window, so that a
+/// deployment copying many files only invalidates each of them once.
+///
+/// ## Example
+///
+/// ```ignore
+/// let static_handler = Static::new(path).set(AssetManifest::build(path)?);
+/// let _watcher = static_handler.watch(Watch::new().debounce(Duration::from_millis(250)))?;
+/// Iron::new(static_handler).http("localhost:3000")?;
+/// ```
+#[derive(Clone)]
+pub struct Watch {
+    debounce: Duration,
+    poll_interval: Duration,
+    polling: bool,
+    on_change: Vec<Arc<ChangeHandler>>,
+}
+
+type ChangeHandler = dyn Fn(&Path) + Send + Sync;
+
+impl Watch {
+    /// Watch with a debounce window of 100 milliseconds, and scan every second if inotify
+    /// can't be used.
+    pub fn new() -> Watch {
+        Watch {
+            debounce: Duration::from_millis(100),
+            poll_interval: Duration::from_secs(1),
+            polling: false,
+            on_change: Vec::new(),
+        }
+    }
+
+    /// Wait until no changes have been seen for `debounce` before handling them.
+    pub fn debounce(mut self, debounce: Duration) -> Watch {
+        self.debounce = debounce;
+        self
+    }
+
+    /// Scan the root for changes every `interval` when inotify can't be used.
+    pub fn poll_interval(mut self, interval: Duration) -> Watch {
+        self.poll_interval = interval;
+        self
+    }
+
+    /// Scan the root for changes even where inotify could be used.
+    pub fn polling(mut self, polling: bool) -> Watch {
+        self.polling = polling;
+        self
+    }
+
+    /// Call `callback` with the path, relative to the root, of every changed file or directory
+    /// once the handler's state for it has been dropped. The root itself is reported as an
+    /// empty path when anything below it may have changed.
+    pub fn on_change<F>(mut self, callback: F) -> Watch
+        where F: Fn(&Path) + Send + Sync + 'static
+    {
+        self.on_change.push(Arc::new(callback));
+        self
+    }
+}
+
+impl Default for Watch {
+    fn default() -> Watch {
+        Watch::new()
+    }
+}
+
+/// The state of a `Static` that depends on the content of its files.
+pub(crate) struct Targets {
+    pub manifest: Option<AssetManifest>,
+    #[cfg(feature = "mmap")]
+    pub mmap: Option<MmapCache>,
+}
+
+/// A running watch started by `Static::watch`, which stops when this is dropped.
+pub struct Watcher {
+    stop: Arc<AtomicBool>,
+    polling: bool,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl Watcher {
+    pub(crate) fn start(root: &Path, targets: Targets, watch: Watch) -> io::Result<Watcher> {
+        let source = Source::new(root, &watch)?;
+        let polling = source.is_polling();
+        let stop = Arc::new(AtomicBool::new(false));
+
+        let stopped = stop.clone();
+        let root = root.to_owned();
+        let thread = thread::Builder::new().name("staticfile-watch-root".to_owned()).spawn(move || {
+            run(source, &root, &targets, &watch, &stopped);
+        })?;
+
+        Ok(Watcher { stop, polling, thread: Some(thread) })
+    }
+
+    /// Returns `true` if changes are found by scanning the root rather than with inotify.
+    pub fn is_polling(&self) -> bool {
+        self.polling
+    }
+}
+
+impl Drop for Watcher {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::SeqCst);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+/// How often the watching thread checks whether it should stop.
+const TICK: Duration = Duration::from_millis(50);
+
+fn run(mut source: Source, root: &Path, targets: &Targets, watch: &Watch, stop: &AtomicBool) {
+    let mut pending = BTreeSet::new();
+    let mut last_change = Instant::now();
+
+    while !stop.load(Ordering::SeqCst) {
+        let timeout = if pending.is_empty() {
+            TICK
+        } else {
+            watch.debounce.checked_sub(last_change.elapsed()).unwrap_or_default().min(TICK)
+        };
+
+        let changes = source.wait(root, timeout).unwrap_or_else(|_| {
+            // If inotify fails, e.g. because it ran out of watches, fall back to scanning
+            // and assume that anything could have changed in the meantime.
+            source = Source::polling(root, watch.poll_interval);
+            vec![PathBuf::new()]
+        });
+        if !changes.is_empty() {
+            pending.extend(changes);
+            last_change = Instant::now();
+        }
+
+        if !pending.is_empty() && last_change.elapsed() >= watch.debounce {
+            for relative in collapse(&pending) {
+                invalidate(root, targets, relative);
+                for callback in &watch.on_change {
+                    callback(relative);
+                }
+            }
+            pending.clear();
+        }
+    }
+}
+
+/// The changed paths without those below another changed directory, in order.
+fn collapse(paths: &BTreeSet<PathBuf>) -> Vec<&Path> {
+    let mut collapsed: Vec<&Path> = Vec::new();
+    for path in paths {
+        if !collapsed.last().is_some_and(|dir| path.starts_with(dir)) {
+            collapsed.push(path);
+        }
+    }
+    collapsed
+}
+
+fn invalidate(root: &Path, targets: &Targets, relative: &Path) {
+    let path = root.join(relative);
+    if let Some(ref manifest) = targets.manifest {
+        manifest.refresh(&path);
+    }
+    #[cfg(feature = "mmap")]
+    {
+        if let Some(ref mmap) = targets.mmap {
+            mmap.invalidate_under(&path);
+        }
+    }
+}
+
+enum Source {
+    #[cfg(all(feature = "watch", target_os = "linux"))]
+    Inotify(native::Watches),
+    Polling(Snapshot),
+}
+
+impl Source {
+    fn new(root: &Path, watch: &Watch) -> io::Result<Source> {
+        if !fs::metadata(root)?.is_dir() {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "the root is not a directory"));
+        }
+
+        #[cfg(all(feature = "watch", target_os = "linux"))]
+        {
+            if !watch.polling && !native::is_remote(root) {
+                if let Ok(watches) = native::Watches::new(root) {
+                    return Ok(Source::Inotify(watches));
+                }
+            }
+        }
+
+        Ok(Source::polling(root, watch.poll_interval))
+    }
+
+    fn polling(root: &Path, interval: Duration) -> Source {
+        Source::Polling(Snapshot::new(root, interval))
+    }
+
+    fn is_polling(&self) -> bool {
+        match *self {
+            #[cfg(all(feature = "watch", target_os = "linux"))]
+            Source::Inotify(_) => false,
+            Source::Polling(_) => true,
+        }
+    }
+
+    /// Wait up to `timeout` for changes, and return the paths that changed relative to `root`.
+    fn wait(&mut self, root: &Path, timeout: Duration) -> io::Result<Vec<PathBuf>> {
+        match *self {
+            #[cfg(all(feature = "watch", target_os = "linux"))]
+            Source::Inotify(ref mut watches) => watches.wait(root, timeout),
+            Source::Polling(ref mut snapshot) => Ok(snapshot.wait(root, timeout)),
+        }
+    }
+}
+
+/// The size and modification time of every file and directory below the root, for finding
+/// changes by scanning.
+struct Snapshot {
+    interval: Duration,
+    next_scan: Instant,
+    entries: HashMap<PathBuf, (u64, Option<SystemTime>)>,
+}
+
+impl Snapshot {
+    fn new(root: &Path, interval: Duration) -> Snapshot {
+        Snapshot { interval, next_scan: Instant::now() + interval, entries: scan(root) }
+    }
+
+    fn wait(&mut self, root: &Path, timeout: Duration) -> Vec<PathBuf> {
+        let now = Instant::now();
+        if now < self.next_scan {
+            thread::sleep(timeout.min(self.next_scan - now));
+            return Vec::new();
+        }
+        self.next_scan = now + self.interval;
+
+        let entries = scan(root);
+        let mut changed: Vec<PathBuf> = entries.iter()
+            .filter(|&(path, stamp)| self.entries.get(path) != Some(stamp))
+            .map(|(path, _)| path.clone())
+            .collect();
+        changed.extend(self.entries.keys().filter(|path| !entries.contains_key(*path)).cloned());
+        self.entries = entries;
+        changed
+    }
+}
+
+fn scan(root: &Path) -> HashMap<PathBuf, (u64, Option<SystemTime>)> {
+    type Entries = HashMap<PathBuf, (u64, Option<SystemTime>)>;
+
+    fn add_dir(root: &Path, dir: &Path, entries: &mut Entries, visited: &mut Visited) {
+        match fs::metadata(dir) {
+            Ok(ref metadata) if visited.enter(dir, metadata) => {},
+            _ => return,
+        }
+        let read_dir = match fs::read_dir(dir) {
+            Ok(read_dir) => read_dir,
+            Err(_) => return,
+        };
+        for entry in read_dir.filter_map(Result::ok) {
+            let path = entry.path();
+            // Files behind symlinks are served, so their changes count; dangling links don't.
+            let metadata = match fs::metadata(&path) {
+                Ok(metadata) => metadata,
+                Err(_) => continue,
+            };
+            if let Ok(relative) = path.strip_prefix(root) {
+                entries.insert(relative.to_owned(), (metadata.len(), metadata.modified().ok()));
+            }
+            if metadata.is_dir() {
+                add_dir(root, &path, entries, visited);
+            }
+        }
+    }
+
+    let mut entries = HashMap::new();
+    add_dir(root, root, &mut entries, &mut Visited::new());
+    entries
+}
+
+#[cfg(all(feature = "watch", target_os = "linux"))]
+mod native {
+    use std::collections::HashMap;
+    use std::ffi::CString;
+    use std::fs;
+    use std::io;
+    use std::mem;
+    use std::os::unix::ffi::OsStrExt;
+    use std::os::unix::io::AsRawFd;
+    use std::path::{Path, PathBuf};
+    use std::time::Duration;
+
+    use inotify::{EventMask, Inotify, WatchDescriptor, WatchMask};
+    use libc;
+
+    use file_id::Visited;
+
+    /// An inotify watch on every directory below the root.
+    pub struct Watches {
+        inotify: Inotify,
+        /// Watched directories relative to the root.
+        dirs: HashMap<WatchDescriptor, PathBuf>,
+        buffer: Vec<u8>,
+    }
+
+    impl Watches {
+        pub fn new(root: &Path) -> io::Result<Watches> {
+            let mut watches = Watches {
+                inotify: Inotify::init()?,
+                dirs: HashMap::new(),
+                buffer: vec![0; 64 * 1024],
+            };
+            watches.add_dir(root, PathBuf::new(), &mut Visited::new())?;
+            Ok(watches)
+        }
+
+        fn add_dir(&mut self, root: &Path, relative: PathBuf, visited: &mut Visited) -> io::Result<()> {
+            let dir = root.join(&relative);
+            if !visited.enter(&dir, &fs::metadata(&dir)?) {
+                return Ok(());
+            }
+            let mask = WatchMask::MODIFY | WatchMask::CLOSE_WRITE | WatchMask::ATTRIB | WatchMask::CREATE
+                | WatchMask::DELETE | WatchMask::MOVE | WatchMask::DELETE_SELF | WatchMask::MOVE_SELF;
+            let wd = self.inotify.watches().add(&dir, mask)?;
+            // A directory found again by a later walk keeps the path it is watched under.
+            if self.dirs.contains_key(&wd) {
+                return Ok(());
+            }
+            self.dirs.insert(wd, relative.clone());
+
+            for entry in fs::read_dir(&dir)?.filter_map(Result::ok) {
+                // Watches don't reach through symlinks, so linked directories need their own.
+                if fs::metadata(entry.path()).map(|metadata| metadata.is_dir()).unwrap_or(false) {
+                    self.add_dir(root, relative.join(entry.file_name()), visited)?;
+                }
+            }
+            Ok(())
+        }
+
+        pub fn wait(&mut self, root: &Path, timeout: Duration) -> io::Result<Vec<PathBuf>> {
+            let mut fd = libc::pollfd { fd: self.inotify.as_raw_fd(), events: libc::POLLIN, revents: 0 };
+            let timeout = timeout.as_millis().min(i32::MAX as u128) as libc::c_int;
+            if unsafe { libc::poll(&mut fd, 1, timeout) } < 0 {
+                let e = io::Error::last_os_error();
+                return if e.kind() == io::ErrorKind::Interrupted { Ok(Vec::new()) } else { Err(e) };
+            }
+
+            let mut changed = Vec::new();
+            let mut old_dirs = Vec::new();
+            let mut new_dirs = Vec::new();
+            loop {
+                let events = match self.inotify.read_events(&mut self.buffer) {
+                    Ok(events) => events,
+                    Err(ref e) if e.kind() == io::ErrorKind::WouldBlock => break,
+                    Err(e) => return Err(e),
+                };
+                for event in events {
+                    if event.mask.contains(EventMask::Q_OVERFLOW) {
+                        // Events were lost, so anything may have changed.
+                        changed.push(PathBuf::new());
+                        continue;
+                    }
+                    if event.mask.contains(EventMask::IGNORED) {
+                        self.dirs.remove(&event.wd);
+                        continue;
+                    }
+                    let dir = match self.dirs.get(&event.wd) {
+                        Some(dir) => dir,
+                        None => continue,
+                    };
+                    let path = match event.name {
+                        Some(name) => dir.join(name),
+                        None => dir.clone(),
+                    };
+                    if event.mask.contains(EventMask::ISDIR) {
+                        if event.mask.contains(EventMask::MOVED_FROM) {
+                            old_dirs.push(path.clone());
+                        } else if event.mask.intersects(EventMask::CREATE | EventMask::MOVED_TO) {
+                            new_dirs.push(path.clone());
+                        }
+                    }
+                    changed.push(path);
+                }
+            }
+
+            // A directory moved away keeps its watches, which would report its files under
+            // the old path.
+            for dir in old_dirs {
+                let moved: Vec<WatchDescriptor> = self.dirs.iter()
+                    .filter(|&(_, watched)| watched.starts_with(&dir))
+                    .map(|(wd, _)| wd.clone())
+                    .collect();
+                for wd in moved {
+                    self.dirs.remove(&wd);
+                    let _ = self.inotify.watches().remove(wd);
+                }
+            }
+            for dir in new_dirs {
+                // The directory may already be gone again, which is reported separately.
+                match self.add_dir(root, dir, &mut Visited::new()) {
+                    Ok(()) => {},
+                    Err(ref e) if e.kind() == io::ErrorKind::NotFound => {},
+                    Err(e) => return Err(e),
+                }
+            }
+            Ok(changed)
+        }
+    }
+
+    /// Returns `true` if `root` is on a filesystem whose changes inotify may not see.
+    pub fn is_remote(root: &Path) -> bool {
+        const NFS: u32 = 0x6969;
+        const SMB: u32 = 0x517b;
+        const CIFS: u32 = 0xff53_4d42;
+        const SMB2: u32 = 0xfe53_4d42;
+        const FUSE: u32 = 0x6573_5546;
+        const V9FS: u32 = 0x0102_1997;
+
+        let path = match CString::new(root.as_os_str().as_bytes()) {
+            Ok(path) => path,
+            Err(_) => return false,
+        };
+        let mut stat: libc::statfs = unsafe { mem::zeroed() };
+        if unsafe { libc::statfs(path.as_ptr(), &mut stat) } != 0 {
+            return false;
+        }
+        matches!(stat.f_type as u32, NFS | SMB | CIFS | SMB2 | FUSE | V9FS)
+    }
+}
@@ -0,0 +1,151 @@
+#[cfg(feature = "gzip")]
+extern crate hyper;
+extern crate iron;
+extern crate iron_test;
+extern crate staticfile;
+
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, UNIX_EPOCH};
+
+use iron::headers::{ByteRangeSpec, IfNoneMatch, Range};
+use iron::method::Method;
+use iron::status::Status;
+use iron::Headers;
+use iron_test::{request, response, ProjectBuilder};
+use staticfile::{AccessLogEntry, LogFormat, LogWriter, Static};
+
+fn logged(root: PathBuf) -> (Static, Arc<Mutex<Vec<AccessLogEntry>>>) {
+    let entries = Arc::new(Mutex::new(Vec::new()));
+    let log = entries.clone();
+    let st = Static::new(root).access_log(move |entry: &AccessLogEntry| log.lock().unwrap().push(entry.clone()));
+    (st, entries)
+}
+
+#[test]
+fn it_should_log_served_files_once_they_are_sent() {
+    let p = ProjectBuilder::new("access_log").file("hello.txt", "hello");
+    p.build();
+    let (st, entries) = logged(p.root().to_path_buf());
+
+    let mut headers = Headers::new();
+    headers.set(Range::Bytes(vec![ByteRangeSpec::FromTo(0, 1)]));
+    let res = request::get("http://localhost:3000/hello.txt?v=1", headers, &st).unwrap();
+    assert!(entries.lock().unwrap().is_empty());
+    assert_eq!(response::extract_body_to_string(res), "hello");
+
+    let entries = entries.lock().unwrap();
+    assert_eq!(entries.len(), 1);
+    let entry = &entries[0];
+    assert_eq!(entry.method, Method::Get);
+    assert_eq!(entry.url, "/hello.txt?v=1");
+    assert_eq!(entry.path, Some(p.root().join("hello.txt")));
+    assert_eq!(entry.status, Status::Ok);
+    assert_eq!(entry.bytes, 5);
+    assert_eq!(entry.range, Some("bytes=0-1".to_owned()));
+    assert_eq!(entry.encoding, None);
+    assert_eq!(entry.length, Some(5));
+    assert!(!entry.not_modified);
+    assert_eq!(entry.cache_hit, None);
+}
+
+#[test]
+fn it_should_log_errors_and_not_modified_responses() {
+    let p = ProjectBuilder::new("access_log_errors").file("hello.txt", "hello");
+    p.build();
+    let (st, entries) = logged(p.root().to_path_buf());
+
+    drop(request::get("http://localhost:3000/missing.txt", Headers::new(), &st).unwrap_err());
+    drop(request::head("http://localhost:3000/hello.txt", Headers::new(), &st).unwrap());
+    let mut headers = Headers::new();
+    headers.set(IfNoneMatch::Any);
+    drop(request::get("http://localhost:3000/hello.txt", headers, &st).unwrap());
+
+    let entries = entries.lock().unwrap();
+    assert_eq!(entries.len(), 3);
+    assert_eq!((entries[0].status, entries[0].path.clone(), entries[0].bytes), (Status::NotFound, None, 0));
+    assert_eq!((entries[1].method.clone(), entries[1].status, entries[1].bytes), (Method::Head, Status::Ok, 0));
+    assert_eq!(entries[2].status, Status::NotModified);
+    assert!(entries[2].not_modified);
+}
+
+#[derive(Clone, Default)]
+struct Lines(Arc<Mutex<Vec<u8>>>);
+
+impl Write for Lines {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.lock().unwrap().write(buf)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+fn entry() -> AccessLogEntry {
+    AccessLogEntry {
+        time: UNIX_EPOCH + Duration::from_secs(1_500_000_000),
+        remote_addr: "10.0.0.1:51234".parse().unwrap(),
+        method: Method::Get,
+        url: "/say \"hi\".txt".to_owned(),
+        version: "HTTP/1.1".to_owned(),
+        path: Some(PathBuf::from("public/say \"hi\".txt")),
+        status: Status::Ok,
+        bytes: 2326,
+        length: Some(7120),
+        range: None,
+        encoding: Some("gzip".to_owned()),
+        not_modified: false,
+        cache_hit: Some(true),
+        latency: Duration::from_micros(1500),
+    }
+}
+
+#[test]
+fn it_should_format_json_lines() {
+    use staticfile::AccessLog;
+
+    let lines = Lines::default();
+    LogWriter::new(LogFormat::Json, lines.clone()).log(&entry());
+    assert_eq!(String::from_utf8(lines.0.lock().unwrap().clone()).unwrap(),
+               "{\"time\":\"2017-07-14T02:40:00Z\",\"remote_addr\":\"10.0.0.1\",\"method\":\"GET\",\
+                \"url\":\"/say \\\"hi\\\".txt\",\"version\":\"HTTP/1.1\",\"path\":\"public/say \\\"hi\\\".txt\",\
+                \"status\":200,\"bytes\":2326,\"length\":7120,\"range\":null,\"encoding\":\"gzip\",\"not_modified\":false,\
+                \"cache_hit\":true,\"latency_ms\":1.500}\n");
+}
+
+#[test]
+fn it_should_format_common_log_lines() {
+    let line = entry().common_log_format();
+    assert!(line.starts_with("10.0.0.1 - - ["), "{}", line);
+    assert!(line.ends_with("] \"GET /say \"hi\".txt HTTP/1.1\" 200 2326"), "{}", line);
+
+    let mut empty = entry();
+    empty.bytes = 0;
+    assert!(empty.common_log_format().ends_with(" 200 -"));
+}
+
+#[cfg(feature = "gzip")]
+#[test]
+fn it_should_log_the_encoding_and_compressed_size() {
+    use hyper::header::{AcceptEncoding, Encoding, qitem};
+    use iron::Set;
+    use staticfile::Compression;
+
+    let p = ProjectBuilder::new("access_log_gzip").file("big.txt", "all work and no play ".repeat(100));
+    p.build();
+    let (st, entries) = logged(p.root().to_path_buf());
+    let st = st.set(Compression::new());
+
+    let mut headers = Headers::new();
+    headers.set(AcceptEncoding(vec![qitem(Encoding::Gzip)]));
+    let res = request::get("http://localhost:3000/big.txt", headers, &st).unwrap();
+    let mut body = Vec::new();
+    res.body.unwrap().write_body(&mut body).unwrap();
+
+    let entries = entries.lock().unwrap();
+    assert_eq!(entries[0].encoding, Some("gzip".to_owned()));
+    assert_eq!(entries[0].bytes, body.len() as u64);
+    assert!(entries[0].bytes < 2100);
+}
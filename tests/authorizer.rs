@@ -0,0 +1,102 @@
+extern crate iron;
+extern crate iron_test;
+extern crate staticfile;
+
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use iron::headers::{Cookie, Headers, Location};
+use iron::prelude::*;
+use iron::status;
+use iron_test::{request, response, ProjectBuilder};
+use staticfile::{Access, Static};
+
+/// Lets requests with a session cookie into `account`, and sends others to the login page.
+fn sessions(req: &Request, path: &Path) -> Access {
+    let signed_in = req.headers.get::<Cookie>().is_some_and(|cookie| cookie.iter().any(|c| c == "session=ok"));
+    if path.starts_with("vault") {
+        Access::Deny
+    } else if path.starts_with("account") && !signed_in {
+        Access::Redirect("/login?next=account".to_owned())
+    } else {
+        Access::Allow
+    }
+}
+
+fn signed_in() -> Headers {
+    let mut headers = Headers::new();
+    headers.set(Cookie(vec!["session=ok".to_owned()]));
+    headers
+}
+
+#[test]
+fn it_should_answer_with_the_decision() {
+    let p = ProjectBuilder::new("authorizer")
+        .file("index.html", "home")
+        .file("account/settings.html", "settings")
+        .file("vault/key.txt", "key");
+    p.build();
+    let st = Static::new(p.root()).authorizer(sessions);
+
+    let res = request::get("http://localhost:3000/", Headers::new(), &st).unwrap();
+    assert_eq!(response::extract_body_to_string(res), "home");
+
+    let res = request::get("http://localhost:3000/account/settings.html", Headers::new(), &st).unwrap();
+    assert_eq!(res.status, Some(status::Found));
+    assert_eq!(res.headers.get::<Location>(), Some(&Location("/login?next=account".to_owned())));
+
+    let res = request::get("http://localhost:3000/account/settings.html", signed_in(), &st).unwrap();
+    assert_eq!(response::extract_body_to_string(res), "settings");
+
+    let err = request::get("http://localhost:3000/vault/key.txt", signed_in(), &st).unwrap_err();
+    assert_eq!(err.response.status, Some(status::Forbidden));
+    // Whether a file exists isn't given away.
+    let err = request::get("http://localhost:3000/vault/missing.txt", signed_in(), &st).unwrap_err();
+    assert_eq!(err.response.status, Some(status::Forbidden));
+}
+
+#[test]
+fn it_should_authorize_normalized_paths() {
+    let p = ProjectBuilder::new("authorizer_paths")
+        .file("public/index.html", "public")
+        .file("account/settings.html", "settings")
+        .file("account/index.html", "account");
+    p.build();
+    let asked = Arc::new(Mutex::new(Vec::new()));
+    let seen = asked.clone();
+    let st = Static::new(p.root()).authorizer(move |req: &Request, path: &Path| {
+        seen.lock().unwrap().push(path.to_path_buf());
+        sessions(req, path)
+    });
+
+    for url in &["/public/../account/settings.html", "/public/%2e%2e/account/settings.html",
+                 "/./account//settings.html", "/%61ccount/settings.html", "/public%2F..%2Faccount/settings.html"] {
+        let res = request::get(&format!("http://localhost:3000{}", url), Headers::new(), &st).unwrap();
+        assert_eq!(res.status, Some(status::Found), "{}", url);
+    }
+    assert_eq!(*asked.lock().unwrap(), vec![PathBuf::from("account/settings.html"); 5]);
+    asked.lock().unwrap().clear();
+
+    let res = request::get("http://localhost:3000/account/", signed_in(), &st).unwrap();
+    assert_eq!(response::extract_body_to_string(res), "account");
+    assert_eq!(*asked.lock().unwrap(), vec![PathBuf::from("account"), PathBuf::from("account/index.html")]);
+}
+
+#[test]
+fn it_should_hide_files_it_refuses_from_listings() {
+    let p = ProjectBuilder::new("authorizer_listing")
+        .file("notes.txt", "notes")
+        .file("account/settings.html", "settings")
+        .file("vault/key.txt", "key");
+    p.build();
+    let st = Static::new(p.root()).autoindex(true).authorizer(sessions);
+
+    let body = response::extract_body_to_string(request::get("http://localhost:3000/", Headers::new(), &st).unwrap());
+    assert!(body.contains("notes.txt"));
+    assert!(!body.contains("account"));
+    assert!(!body.contains("vault"));
+
+    let body = response::extract_body_to_string(request::get("http://localhost:3000/", signed_in(), &st).unwrap());
+    assert!(body.contains("account/"));
+    assert!(!body.contains("vault"));
+}
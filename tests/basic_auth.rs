@@ -0,0 +1,141 @@
+extern crate iron;
+extern crate iron_test;
+extern crate staticfile;
+
+#[cfg(feature = "auth")]
+mod basic_auth {
+    use std::io::ErrorKind;
+
+    use iron::headers::{Authorization, Basic, Headers};
+    use iron::status;
+    use iron_test::{request, response, ProjectBuilder};
+    use staticfile::{BasicAuth, Htpasswd, Static};
+
+    const USERS: &str = "\
+# Staff
+alice:$apr1$r31.....$HqJZimcKQFAMYayBlzkrA/
+bob:{SHA}W6ph5Mm5Pz8GgiULbPgzG37mj9g=
+
+carol:$2y$05$CCCCCCCCCCCCCCCCCCCCC.E5YPO9kmyuRGyh0XouQYb4YMJKvyOeW
+dave:$1$saltsalt$9xy1btjgzLYfb7hivXtC//
+";
+
+    fn as_user(user: &str, password: &str) -> Headers {
+        let mut headers = Headers::new();
+        headers.set(Authorization(Basic { username: user.to_owned(), password: Some(password.to_owned()) }));
+        headers
+    }
+
+    #[test]
+    fn it_should_verify_htpasswd_hashes() {
+        let users = Htpasswd::parse(USERS).unwrap();
+        assert!(users.verify("alice", "myPassword"));
+        assert!(users.verify("bob", "password"));
+        assert!(users.verify("carol", "U*U"));
+        assert!(users.verify("dave", "secret"));
+
+        assert!(!users.verify("alice", "mypassword"));
+        assert!(!users.verify("bob", "password "));
+        assert!(!users.verify("carol", "U*V"));
+        assert!(!users.verify("alice", "password"));
+        assert!(!users.verify("eve", "myPassword"));
+    }
+
+    #[test]
+    fn it_should_refuse_unsupported_htpasswd_lines() {
+        let e = Htpasswd::parse("alice:$apr1$r31.....$HqJZimcKQFAMYayBlzkrA/\nbob:plaintext\n").unwrap_err();
+        assert_eq!(e.kind(), ErrorKind::InvalidData);
+        assert_eq!(e.to_string(), "line 2: unsupported password hash for bob");
+
+        let e = Htpasswd::parse("alice").unwrap_err();
+        assert_eq!(e.to_string(), "line 1: expected user:hash");
+    }
+
+    #[test]
+    fn it_should_challenge_requests_for_protected_files() {
+        let p = ProjectBuilder::new("basic_auth")
+            .file("index.html", "public")
+            .file("docs/internal/plans.txt", "plans")
+            .file("docs/internal/index.html", "internal");
+        p.build();
+        let auth = BasicAuth::new("Staff \"only\"", Htpasswd::parse(USERS).unwrap());
+        let st = Static::new(p.root()).basic_auth("**/internal", auth);
+
+        let res = request::get("http://localhost:3000/", Headers::new(), &st).unwrap();
+        assert_eq!(response::extract_body_to_string(res), "public");
+
+        for url in &["/docs/internal/plans.txt", "/docs/internal/", "/docs/internal/missing.txt",
+                     "/docs/./x/../internal/plans.txt", "/docs/%69nternal/plans.txt"] {
+            let err = request::get(&format!("http://localhost:3000{}", url), Headers::new(), &st).unwrap_err();
+            assert_eq!(err.response.status, Some(status::Unauthorized), "{}", url);
+            assert_eq!(err.response.headers.get_raw("WWW-Authenticate").unwrap(),
+                       &[b"Basic realm=\"Staff \\\"only\\\"\", charset=\"UTF-8\"".to_vec()]);
+        }
+
+        let err = request::get("http://localhost:3000/docs/internal/plans.txt", as_user("alice", "nope"), &st).unwrap_err();
+        assert_eq!(err.response.status, Some(status::Unauthorized));
+
+        let res = request::get("http://localhost:3000/docs/internal/plans.txt", as_user("alice", "myPassword"), &st).unwrap();
+        assert_eq!(response::extract_body_to_string(res), "plans");
+        let res = request::get("http://localhost:3000/docs/internal/", as_user("bob", "password"), &st).unwrap();
+        assert_eq!(response::extract_body_to_string(res), "internal");
+    }
+
+    #[test]
+    fn it_should_not_serve_protected_files_by_another_case() {
+        let p = ProjectBuilder::new("basic_auth_case")
+            .file("docs/internal/plans.txt", "plans");
+        p.build();
+        let auth = BasicAuth::new("Staff", Htpasswd::parse(USERS).unwrap());
+        let st = Static::new(p.root()).basic_auth("**/internal", auth);
+
+        // Where filesystems ignore case this opens the protected file, so it must be protected
+        // too; elsewhere there is no such file.
+        let expected = if cfg!(any(target_os = "macos", target_os = "ios", target_os = "windows")) {
+            status::Unauthorized
+        } else {
+            status::NotFound
+        };
+        let err = request::get("http://localhost:3000/docs/INTERNAL/plans.txt", Headers::new(), &st).unwrap_err();
+        assert_eq!(err.response.status, Some(expected));
+    }
+
+    #[test]
+    fn it_should_protect_index_files_of_unprotected_urls() {
+        let p = ProjectBuilder::new("basic_auth_index")
+            .file("drafts/index.html", "draft")
+            .file("drafts/notes.txt", "notes");
+        p.build();
+        let auth = BasicAuth::new("Drafts", Htpasswd::parse(USERS).unwrap());
+        let st = Static::new(p.root()).basic_auth("drafts/index.html", auth);
+
+        let err = request::get("http://localhost:3000/drafts/", Headers::new(), &st).unwrap_err();
+        assert_eq!(err.response.status, Some(status::Unauthorized));
+        let res = request::get("http://localhost:3000/drafts/notes.txt", Headers::new(), &st).unwrap();
+        assert_eq!(response::extract_body_to_string(res), "notes");
+    }
+
+    #[test]
+    fn it_should_hide_protected_files_from_listings() {
+        let p = ProjectBuilder::new("basic_auth_listing")
+            .file("docs/guide.txt", "guide")
+            .file("docs/secret.txt", "secret")
+            .file("docs/internal/plans.txt", "plans");
+        p.build();
+        let users = Htpasswd::parse(USERS).unwrap();
+        let st = Static::new(p.root()).autoindex(true)
+            .basic_auth("**/internal", BasicAuth::new("Staff", users.clone()))
+            .basic_auth("**/secret.txt", BasicAuth::new("Secrets", users));
+
+        let res = request::get("http://localhost:3000/docs/", Headers::new(), &st).unwrap();
+        let body = response::extract_body_to_string(res);
+        assert!(body.contains("guide.txt"));
+        assert!(!body.contains("internal"));
+        assert!(!body.contains("secret.txt"));
+
+        let res = request::get("http://localhost:3000/docs/", as_user("dave", "secret"), &st).unwrap();
+        let body = response::extract_body_to_string(res);
+        assert!(body.contains("<a href=\"internal/\">internal/</a>"));
+        assert!(body.contains("<a href=\"secret.txt\">secret.txt</a>"));
+    }
+}
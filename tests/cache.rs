@@ -1,5 +1,11 @@
+// `ProjectBuilder::root` returns a `&Path`, which these tests have always cloned.
+#![allow(noop_method_call)]
+
 extern crate time;
 
+#[cfg(feature = "cache")]
+extern crate filetime;
+
 extern crate hyper;
 extern crate iron;
 extern crate iron_test;
@@ -8,18 +14,20 @@ extern crate staticfile;
 #[cfg(feature = "cache")]
 mod cache {
     use time;
+    use filetime::{self, FileTime};
     use time::{Timespec};
 
     #[cfg(feature = "cache")]
     use std::time::Duration;
 
-    use iron::Headers;
+    use iron::{Headers, Set};
     use iron::status::Status;
     use iron::headers::HttpDate;
-    use hyper::header::{IfModifiedSince, CacheControl, CacheDirective, LastModified, ETag};
+    use hyper::header::{IfModifiedSince, CacheControl, CacheDirective, LastModified, ETag, Expires, Date};
+    use hyper::header::{ContentLength, ContentType};
     use iron_test::ProjectBuilder;
     use iron_test::request;
-    use staticfile::Static;
+    use staticfile::{Cache, CachePolicy, SecurityHeaders, Static};
 
     #[cfg(feature = "cache")]
     #[test]
@@ -131,4 +139,221 @@ mod cache {
             Err(e) => panic!("{}", e)
         }
     }
+
+    #[cfg(feature = "cache")]
+    #[test]
+    fn it_should_send_the_same_content_type_for_head_and_get() {
+        let p = ProjectBuilder::new("example").file("app.js", "var a = 1;");
+        p.build();
+
+        let st = Static::new(p.root()).cache(Duration::from_secs(30*24*60*60));
+        let get = request::get("http://localhost:3000/app.js", Headers::new(), &st).unwrap();
+        let mut headers = Headers::new();
+        headers.set(ContentType::html());
+        let head = request::head("http://localhost:3000/app.js", headers, &st).unwrap();
+
+        assert_eq!(head.status.unwrap(), Status::Ok);
+        assert_eq!(head.headers.get::<ContentType>(), get.headers.get::<ContentType>());
+        assert_eq!(head.headers.get::<ContentLength>(), Some(&ContentLength(10)));
+    }
+
+    #[cfg(feature = "cache")]
+    #[test]
+    fn it_should_send_the_same_validators_for_head_and_get() {
+        let p = ProjectBuilder::new("example").file("file1.html", "this is file1");
+        p.build();
+
+        let st = Static::new(p.root()).cache(Duration::from_secs(30*24*60*60));
+        let get = request::get("http://localhost:3000/file1.html", Headers::new(), &st).unwrap();
+        let head = request::head("http://localhost:3000/file1.html", Headers::new(), &st).unwrap();
+
+        assert_eq!(head.headers.get::<ETag>(), get.headers.get::<ETag>());
+        assert_eq!(head.headers.get::<LastModified>(), get.headers.get::<LastModified>());
+        assert_eq!(head.headers.get::<CacheControl>(), get.headers.get::<CacheControl>());
+        assert_eq!(head.headers.get::<ContentLength>(), get.headers.get::<ContentLength>());
+
+        let mut headers = Headers::new();
+        headers.set(IfModifiedSince(HttpDate(time::now_utc())));
+        let head = request::head("http://localhost:3000/file1.html", headers, &st).unwrap();
+        assert_eq!(head.status.unwrap(), Status::NotModified);
+    }
+
+    #[cfg(feature = "cache")]
+    #[test]
+    fn it_should_send_security_headers_with_304() {
+        let p = ProjectBuilder::new("example").file("file1.html", "this is file1");
+        p.build();
+
+        let st = Static::new(p.root())
+            .cache(Duration::from_secs(30*24*60*60))
+            .set(SecurityHeaders::new().mime("text/html", "Content-Security-Policy", Some("default-src 'self'")));
+        let mut headers = Headers::new();
+        headers.set(IfModifiedSince(HttpDate(time::now_utc())));
+        let res = request::get("http://localhost:3000/file1.html", headers, &st).unwrap();
+
+        assert_eq!(res.status.unwrap(), Status::NotModified);
+        assert_eq!(res.headers.get_raw("Content-Security-Policy").unwrap()[0], b"default-src 'self'".to_vec());
+    }
+
+    #[cfg(feature = "cache")]
+    #[test]
+    fn it_should_apply_the_first_matching_rule() {
+        let p = ProjectBuilder::new("example")
+            .file("index.html", "<p>hi</p>")
+            .file("assets/app-3f9a2c1b.js", "go()")
+            .file("private/report.csv", "a,b")
+            .file("logo.png", "png");
+        p.build();
+
+        let year = Duration::from_secs(365*24*60*60);
+        let cache = Cache::new(Duration::from_secs(3600))
+            .glob("/private/**", CachePolicy::new().private().no_store().expires(true))
+            .mime("text/html", CachePolicy::new().no_cache())
+            .glob("/assets/*-[0-9a-f]*.*", CachePolicy::new().public().max_age(year).immutable())
+            .mime("text/*", CachePolicy::new().public().max_age(Duration::from_secs(60))
+                  .s_maxage(Duration::from_secs(600))
+                  .stale_while_revalidate(Duration::from_secs(30))
+                  .must_revalidate());
+        let st = Static::new(p.root()).set(cache);
+
+        let cache_control = |path: &str| {
+            let url = format!("http://localhost:3000/{}", path);
+            let res = request::get(&url, Headers::new(), &st).unwrap();
+            (res.headers.get::<CacheControl>().unwrap().to_string(), res.headers.get::<Expires>().cloned())
+        };
+
+        assert_eq!(cache_control("index.html"), ("no-cache".to_owned(), None));
+        assert_eq!(cache_control("assets/app-3f9a2c1b.js").0, "public, max-age=31536000, immutable");
+        assert_eq!(cache_control("logo.png").0, "public, max-age=3600");
+
+        let (private, expires) = cache_control("private/report.csv");
+        assert_eq!(private, "private, no-store");
+        assert_eq!(expires.unwrap().to_string(), "Thu, 01 Jan 1970 00:00:00 GMT");
+    }
+
+    #[cfg(feature = "cache")]
+    #[test]
+    fn it_should_send_the_policy_with_listings() {
+        let p = ProjectBuilder::new("example").file("docs/notes.txt", "notes");
+        p.build();
+        let cache = Cache::new(Duration::from_secs(3600)).glob("/docs", CachePolicy::new().no_cache());
+        let st = Static::new(p.root()).autoindex(true).set(cache);
+
+        let res = request::get("http://localhost:3000/", Headers::new(), &st).unwrap();
+        assert_eq!(res.headers.get::<CacheControl>().unwrap().to_string(), "public, max-age=3600");
+        let res = request::get("http://localhost:3000/docs/", Headers::new(), &st).unwrap();
+        assert_eq!(res.headers.get::<CacheControl>().unwrap().to_string(), "no-cache");
+    }
+
+    #[cfg(feature = "cache")]
+    #[test]
+    fn it_should_send_the_policy_with_304() {
+        let p = ProjectBuilder::new("example").file("data.txt", "data");
+        p.build();
+
+        let policy = CachePolicy::new().public().max_age(Duration::from_secs(60))
+            .s_maxage(Duration::from_secs(600))
+            .stale_while_revalidate(Duration::from_secs(30))
+            .must_revalidate()
+            .expires(true);
+        let st = Static::new(p.root()).set(Cache::new(Duration::from_secs(3600)).mime("text/*", policy));
+
+        let mut headers = Headers::new();
+        headers.set(IfModifiedSince(HttpDate(time::now_utc())));
+        let res = request::get("http://localhost:3000/data.txt", headers, &st).unwrap();
+
+        assert_eq!(res.status.unwrap(), Status::NotModified);
+        assert_eq!(res.headers.get::<CacheControl>().unwrap().to_string(),
+                   "public, max-age=60, s-maxage=600, stale-while-revalidate=30, must-revalidate");
+        let Expires(HttpDate(expires)) = res.headers.get::<Expires>().cloned().unwrap();
+        assert!(expires.to_timespec() > time::now_utc().to_timespec());
+    }
+
+    #[cfg(feature = "cache")]
+    #[test]
+    fn it_should_cache_fingerprinted_files_forever() {
+        let p = ProjectBuilder::new("example")
+            .file("index.html", "<p>hi</p>")
+            .file("main.3f9a2c1b.js", "go()")
+            .file("css/app-0123abcd4567ef89.min.css", "p {}")
+            .file("css/site.css", "p {}")
+            .file("js/index-Dk3Jt9wL.js", "go()");
+        p.build();
+
+        let cache_control = |st: &Static, path: &str| {
+            let url = format!("http://localhost:3000/{}", path);
+            let res = request::get(&url, Headers::new(), st).unwrap();
+            res.headers.get::<CacheControl>().unwrap().to_string()
+        };
+
+        let st = Static::new(p.root()).set(Cache::new(Duration::from_secs(60))
+            .mime("text/html", CachePolicy::new().no_cache())
+            .fingerprinted());
+        assert_eq!(cache_control(&st, "main.3f9a2c1b.js"), "public, max-age=31536000, immutable");
+        assert_eq!(cache_control(&st, "css/app-0123abcd4567ef89.min.css"), "public, max-age=31536000, immutable");
+        assert_eq!(cache_control(&st, "css/site.css"), "public, max-age=60");
+        assert_eq!(cache_control(&st, "js/index-Dk3Jt9wL.js"), "public, max-age=60");
+        assert_eq!(cache_control(&st, "index.html"), "no-cache");
+
+        let st = Static::new(p.root()).set(Cache::new(Duration::from_secs(60))
+            .fingerprint_pattern(r"-[A-Za-z0-9_-]{8}\.js$"));
+        assert_eq!(cache_control(&st, "js/index-Dk3Jt9wL.js"), "public, max-age=31536000, immutable");
+        assert_eq!(cache_control(&st, "main.3f9a2c1b.js"), "public, max-age=60");
+    }
+
+    #[cfg(feature = "cache")]
+    #[test]
+    fn it_should_tell_apart_modifications_within_a_second() {
+        let p = ProjectBuilder::new("example").file("file1.txt", "one").file("file2.txt", "two");
+        p.build();
+
+        let st = Static::new(p.root()).cache(Duration::from_secs(60));
+        let etag = |name: &str, nanos: u32| {
+            let path = p.root().join(name);
+            let time = FileTime::from_seconds_since_1970(1_500_000_000, nanos);
+            filetime::set_file_times(&path, time, time).unwrap();
+            let url = format!("http://localhost:3000/{}", name);
+            let res = request::get(&url, Headers::new(), &st).unwrap();
+            (res.headers.get::<ETag>().unwrap().clone(), res.headers.get::<LastModified>().unwrap().clone())
+        };
+
+        let (first_etag, first_modified) = etag("file1.txt", 100_000_000);
+        let (second_etag, second_modified) = etag("file2.txt", 600_000_000);
+        assert_eq!(first_etag.tag(), "3-59682f00.5f5e100");
+        assert_ne!(first_etag, second_etag);
+        assert_eq!(first_modified, second_modified);
+        assert_eq!(first_modified.to_string(), "Fri, 14 Jul 2017 02:40:00 GMT");
+    }
+
+    #[cfg(feature = "cache")]
+    #[test]
+    fn it_should_not_send_a_last_modified_date_from_the_future() {
+        let p = ProjectBuilder::new("example").file("file1.txt", "this is file1");
+        p.build();
+
+        let tomorrow = time::get_time().sec as u64 + 24*60*60;
+        let time = FileTime::from_seconds_since_1970(tomorrow, 0);
+        filetime::set_file_times(p.root().join("file1.txt"), time, time).unwrap();
+
+        let st = Static::new(p.root()).cache(Duration::from_secs(60));
+        let res = request::get("http://localhost:3000/file1.txt", Headers::new(), &st).unwrap();
+        let LastModified(HttpDate(modified)) = *res.headers.get::<LastModified>().unwrap();
+        let Date(HttpDate(date)) = *res.headers.get::<Date>().unwrap();
+        assert!(modified.to_timespec() <= date.to_timespec());
+    }
+
+    #[cfg(feature = "cache")]
+    #[test]
+    fn it_should_ignore_if_modified_since_dates_from_the_future() {
+        let p = ProjectBuilder::new("example").file("file1.txt", "this is file1");
+        p.build();
+
+        let st = Static::new(p.root()).cache(Duration::from_secs(60));
+        let tomorrow = Timespec::new(time::get_time().sec + 24*60*60, 0);
+        let mut headers = Headers::new();
+        headers.set(IfModifiedSince(HttpDate(time::at_utc(tomorrow))));
+        let res = request::get("http://localhost:3000/file1.txt", headers, &st).unwrap();
+
+        assert_eq!(res.status.unwrap(), Status::Ok);
+    }
 }
@@ -0,0 +1,38 @@
+#[cfg(feature = "cli")]
+mod cli {
+    use std::process::Command;
+
+    #[test]
+    fn it_should_print_usage() {
+        let output = Command::new(env!("CARGO_BIN_EXE_staticfile")).arg("--help").output().unwrap();
+        assert!(output.status.success());
+        let usage = String::from_utf8(output.stdout).unwrap();
+        for flag in &["--bind", "--port", "--cache", "--index", "--autoindex", "--spa", "--cors", "--compress", "--live-reload", "--mount", "--config"] {
+            assert!(usage.contains(flag), "{} is missing from the usage", flag);
+        }
+    }
+
+    #[test]
+    fn it_should_reject_invalid_arguments() {
+        for args in &[&["--mount", "docs"][..], &["--port", "http"][..], &["no/such/dir"][..],
+                      &["--config", "no/such/staticfile.toml"][..], &["--config", "staticfile.toml", "."][..]] {
+            let output = Command::new(env!("CARGO_BIN_EXE_staticfile")).args(*args).output().unwrap();
+            assert_eq!(output.status.code(), Some(2));
+            assert!(String::from_utf8(output.stderr).unwrap().starts_with("staticfile: "));
+        }
+    }
+
+    #[test]
+    fn it_should_reject_handler_options_with_a_config() {
+        for args in &[&["--cache", "60"][..], &["--cors", "*"][..], &["--compress"][..], &["--autoindex"][..]] {
+            let output = Command::new(env!("CARGO_BIN_EXE_staticfile"))
+                .args(["--config", "no/such/staticfile.toml"])
+                .args(*args)
+                .output()
+                .unwrap();
+            assert_eq!(output.status.code(), Some(2));
+            let stderr = String::from_utf8(output.stderr).unwrap();
+            assert!(stderr.starts_with(&format!("staticfile: --config can't be combined with {}", args[0])), "{}", stderr);
+        }
+    }
+}
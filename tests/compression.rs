@@ -0,0 +1,87 @@
+#[cfg(feature = "gzip")]
+extern crate flate2;
+extern crate hyper;
+extern crate iron;
+extern crate iron_test;
+extern crate staticfile;
+
+#[cfg(feature = "gzip")]
+mod compression {
+    use std::io::Read;
+
+    use flate2::read::GzDecoder;
+    use hyper::header::{AcceptEncoding, ContentEncoding, ContentLength, Encoding, ETag, IfNoneMatch, QualityItem, q, qitem};
+    use iron::{status, Headers};
+    use iron::prelude::*;
+    use iron_test::{request, ProjectBuilder};
+    use staticfile::{Compression, Static};
+
+    fn accepting(encodings: Vec<QualityItem<Encoding>>) -> Headers {
+        let mut headers = Headers::new();
+        headers.set(AcceptEncoding(encodings));
+        headers
+    }
+
+    fn body(res: Response) -> Vec<u8> {
+        let mut body = Vec::new();
+        res.body.unwrap().write_body(&mut body).unwrap();
+        body
+    }
+
+    #[test]
+    fn it_should_gzip_compressible_files() {
+        let text = "all work and no play makes jack a dull boy\n".repeat(100);
+        let p = ProjectBuilder::new("example").file("story.txt", text.clone());
+        p.build();
+        let st = Static::new(p.root()).set(Compression::new());
+
+        let plain = request::get("http://localhost:3000/story.txt", Headers::new(), &st).unwrap();
+        assert!(plain.headers.get::<ContentEncoding>().is_none());
+        assert_eq!(plain.headers.get_raw("Vary").unwrap()[0], b"Accept-Encoding".to_vec());
+        let etag = plain.headers.get::<ETag>().cloned();
+
+        let res = request::get("http://localhost:3000/story.txt", accepting(vec![qitem(Encoding::Gzip)]), &st).unwrap();
+        assert_eq!(res.headers.get::<ContentEncoding>(), Some(&ContentEncoding(vec![Encoding::Gzip])));
+        assert!(res.headers.get::<ContentLength>().is_none());
+        assert_eq!(res.headers.get::<ETag>().cloned(), etag);
+        let compressed = body(res);
+        assert!(compressed.len() < text.len() / 10);
+        let mut decoded = String::new();
+        GzDecoder::new(&compressed[..]).read_to_string(&mut decoded).unwrap();
+        assert_eq!(decoded, text);
+
+        let res = request::head("http://localhost:3000/story.txt", accepting(vec![qitem(Encoding::Gzip)]), &st).unwrap();
+        assert_eq!(res.headers.get::<ContentEncoding>(), Some(&ContentEncoding(vec![Encoding::Gzip])));
+        assert!(body(res).is_empty());
+
+        let refused = accepting(vec![QualityItem::new(Encoding::Gzip, q(0.0)), qitem(Encoding::Identity)]);
+        let res = request::get("http://localhost:3000/story.txt", refused, &st).unwrap();
+        assert!(res.headers.get::<ContentEncoding>().is_none());
+        // A 304 varies like the response it stands for, without being compressed.
+        let mut revalidate = accepting(vec![qitem(Encoding::Gzip)]);
+        revalidate.set(IfNoneMatch::Items(vec![etag.unwrap().0]));
+        let res = request::get("http://localhost:3000/story.txt", revalidate, &st).unwrap();
+        assert_eq!(res.status, Some(status::NotModified));
+        assert_eq!(res.headers.get_raw("Vary").unwrap()[0], b"Accept-Encoding".to_vec());
+        assert!(res.headers.get::<ContentEncoding>().is_none());
+    }
+
+    #[test]
+    fn it_should_leave_small_and_incompressible_files_alone() {
+        let p = ProjectBuilder::new("example")
+            .file("short.txt", "short")
+            .file("photo.jpg", vec![0xff; 4096]);
+        p.build();
+        let st = Static::new(p.root()).set(Compression::new());
+
+        for path in &["short.txt", "photo.jpg"] {
+            let url = format!("http://localhost:3000/{}", path);
+            let res = request::get(&url, accepting(vec![qitem(Encoding::Gzip)]), &st).unwrap();
+            assert!(res.headers.get::<ContentEncoding>().is_none());
+        }
+
+        let st = Static::new(p.root()).set(Compression::new().min_size(0));
+        let res = request::get("http://localhost:3000/short.txt", accepting(vec![qitem(Encoding::Gzip)]), &st).unwrap();
+        assert!(res.headers.get::<ContentEncoding>().is_some());
+    }
+}
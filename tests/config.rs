@@ -0,0 +1,110 @@
+extern crate iron;
+extern crate iron_test;
+extern crate staticfile;
+
+#[cfg(feature = "config")]
+mod config {
+    use std::fs;
+
+    use iron::Headers;
+    use iron::headers::Location;
+    use iron::status::Status;
+    use iron_test::{request, ProjectBuilder};
+    use staticfile::Config;
+
+    fn project(name: &str) -> ProjectBuilder {
+        let p = ProjectBuilder::new(name)
+            .file("public/index.html", "<p>home</p>")
+            .file("public/app.js.map", "map")
+            .file("docs/guide.txt", "guide");
+        p.build();
+        p
+    }
+
+    #[test]
+    fn builds_a_mount_from_toml() {
+        let p = project("config_toml");
+        let config = Config::from_toml(&format!(r#"
+            [[mount]]
+            root = "{0}/public"
+            deny = ["*.map"]
+
+            [mount.cache]
+            max_age = 60
+            rules = [{{ mime = "text/html", cache_control = "no-cache" }}]
+
+            [[mount.headers]]
+            name = "X-Frame-Options"
+            value = "DENY"
+
+            [[mount.redirects]]
+            from = "/old"
+            to = "/"
+            status = 308
+
+            [[mount]]
+            prefix = "/docs"
+            root = "{0}/docs"
+        "#, p.root().display())).unwrap();
+        let mount = config.mount();
+
+        let res = request::get("http://localhost:3000/", Headers::new(), &mount).unwrap();
+        assert_eq!(res.status, Some(Status::Ok));
+        assert_eq!(res.headers.get_raw("x-frame-options").unwrap()[0], b"DENY".to_vec());
+        assert_eq!(res.headers.get_raw("cache-control").unwrap()[0], b"no-cache".to_vec());
+
+        let res = request::get("http://localhost:3000/old", Headers::new(), &mount).unwrap();
+        assert_eq!(res.status, Some(Status::PermanentRedirect));
+        assert_eq!(res.headers.get::<Location>(), Some(&Location("/".to_owned())));
+
+        let e = request::get("http://localhost:3000/app.js.map", Headers::new(), &mount).unwrap_err();
+        assert_eq!(e.response.status, Some(Status::NotFound));
+
+        let res = request::get("http://localhost:3000/docs/guide.txt", Headers::new(), &mount).unwrap();
+        assert_eq!(res.status, Some(Status::Ok));
+        assert!(res.headers.get_raw("x-frame-options").is_none());
+    }
+
+    #[test]
+    fn builds_a_mount_from_yaml_file() {
+        let p = project("config_yaml");
+        let path = p.root().join("staticfile.yml");
+        fs::write(&path, format!("
+mount:
+  - prefix: /docs
+    root: {}/docs
+    headers:
+      - name: X-Robots-Tag
+        value: noindex
+        glob: '*.txt'
+", p.root().display())).unwrap();
+        let mount = Config::from_file(&path).unwrap().mount();
+
+        let res = request::get("http://localhost:3000/docs/guide.txt", Headers::new(), &mount).unwrap();
+        assert_eq!(res.headers.get_raw("x-robots-tag").unwrap()[0], b"noindex".to_vec());
+    }
+
+    #[test]
+    fn reports_the_key_and_line_of_invalid_values() {
+        let p = project("config_errors");
+        let root = p.root().join("public");
+
+        let e = Config::from_toml(&format!("[[mount]]\nroot = {:?}\ndeny = [\"[bad\"]\n", root)).unwrap_err();
+        assert_eq!(e.line(), Some(3));
+        let message = e.to_string();
+        assert!(message.contains("mount.deny") && message.contains("invalid glob"), "{}", message);
+
+        let e = Config::from_toml("[[mount]]\nroot = \"/nonexistent/staticfile\"\n").unwrap_err();
+        assert!(e.to_string().contains("is not a directory"), "{}", e);
+
+        let e = Config::from_yaml(&format!("mount:\n  - root: {}\n    redirects:\n      - from: /a\n        to: /b\n        status: 200\n",
+                                           root.display())).unwrap_err();
+        assert_eq!(e.line(), Some(6));
+        let message = e.to_string();
+        assert!(message.contains("mount[0].redirects[0].status") && message.contains("invalid redirect status 200"),
+                "{}", message);
+
+        let e = Config::from_yaml(&format!("mount:\n  - root: {}\n    autoindx: true\n", root.display())).unwrap_err();
+        assert!(e.to_string().contains("unknown field `autoindx`"), "{}", e);
+    }
+}
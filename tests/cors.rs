@@ -0,0 +1,136 @@
+extern crate iron;
+extern crate iron_test;
+extern crate regex;
+extern crate staticfile;
+
+use std::time::Duration;
+
+use iron::Headers;
+use iron::prelude::*;
+use iron::status::Status;
+use iron_test::{request, ProjectBuilder};
+use regex::Regex;
+use staticfile::{AllowedOrigin, Cors, Static};
+
+fn raw<'a>(res: &'a Response, name: &str) -> Option<&'a str> {
+    res.headers.get_raw(name).map(|values| std::str::from_utf8(&values[0]).unwrap())
+}
+
+fn from(origin: &str) -> Headers {
+    let mut headers = Headers::new();
+    headers.set_raw("Origin", vec![origin.as_bytes().to_vec()]);
+    headers
+}
+
+#[test]
+fn reflects_allowed_origins() {
+    let p = ProjectBuilder::new("example").file("fonts/a.woff2", "font");
+    p.build();
+    let cors = Cors::new()
+        .allow_origin(AllowedOrigin::Exact("https://app.example.com".to_owned()))
+        .allow_origin(AllowedOrigin::Wildcard("https://*.example.org".to_owned()))
+        .allow_origin(AllowedOrigin::Regex(Regex::new(r"^https://pr-\d+\.preview\.dev$").unwrap()))
+        .expose_header("ETag")
+        .expose_header("Content-Range");
+    let st = Static::new(p.root()).set(cors);
+
+    for origin in &["https://app.example.com", "https://cdn.example.org", "https://pr-42.preview.dev"] {
+        let res = request::get("http://localhost:3000/fonts/a.woff2", from(origin), &st).unwrap();
+        assert_eq!(raw(&res, "Access-Control-Allow-Origin"), Some(*origin));
+        assert_eq!(raw(&res, "Access-Control-Expose-Headers"), Some("ETag, Content-Range"));
+        assert_eq!(raw(&res, "Vary"), Some("Origin"));
+    }
+
+    let res = request::get("http://localhost:3000/fonts/a.woff2", from("https://evil.com"), &st).unwrap();
+    assert_eq!(raw(&res, "Access-Control-Allow-Origin"), None);
+    assert_eq!(raw(&res, "Vary"), Some("Origin"));
+}
+
+#[test]
+fn sends_a_wildcard_when_any_origin_is_allowed() {
+    let p = ProjectBuilder::new("example").file("data.json", "{}");
+    p.build();
+    let st = Static::new(p.root()).set(Cors::new().allow_origin(AllowedOrigin::Any));
+
+    let res = request::get("http://localhost:3000/data.json", from("https://anywhere.net"), &st).unwrap();
+    assert_eq!(raw(&res, "Access-Control-Allow-Origin"), Some("*"));
+    assert_eq!(raw(&res, "Vary"), None);
+
+    // Any origin never gets credentials.
+    let st = Static::new(p.root()).set(Cors::new().allow_origin(AllowedOrigin::Any).credentials(true));
+    let res = request::get("http://localhost:3000/data.json", from("https://anywhere.net"), &st).unwrap();
+    assert_eq!(raw(&res, "Access-Control-Allow-Origin"), Some("*"));
+    assert_eq!(raw(&res, "Access-Control-Allow-Credentials"), None);
+}
+
+#[test]
+fn allows_credentials_only_for_listed_origins() {
+    let p = ProjectBuilder::new("example").file("data.json", "{}");
+    p.build();
+    let cors = Cors::new()
+        .allow_origin(AllowedOrigin::Any)
+        .allow_origin(AllowedOrigin::Exact("https://app.example.com".to_owned()))
+        .credentials(true);
+    let st = Static::new(p.root()).set(cors);
+
+    let res = request::get("http://localhost:3000/data.json", from("https://app.example.com"), &st).unwrap();
+    assert_eq!(raw(&res, "Access-Control-Allow-Origin"), Some("https://app.example.com"));
+    assert_eq!(raw(&res, "Access-Control-Allow-Credentials"), Some("true"));
+    assert_eq!(raw(&res, "Vary"), Some("Origin"));
+
+    let res = request::get("http://localhost:3000/data.json", from("https://evil.com"), &st).unwrap();
+    assert_eq!(raw(&res, "Access-Control-Allow-Origin"), Some("*"));
+    assert_eq!(raw(&res, "Access-Control-Allow-Credentials"), None);
+    assert_eq!(raw(&res, "Vary"), Some("Origin"));
+
+    let mut headers = from("https://evil.com");
+    headers.set_raw("Access-Control-Request-Method", vec![b"GET".to_vec()]);
+    let res = request::options("http://localhost:3000/data.json", headers, &st).unwrap();
+    assert_eq!(raw(&res, "Access-Control-Allow-Origin"), Some("*"));
+    assert_eq!(raw(&res, "Access-Control-Allow-Credentials"), None);
+}
+
+#[test]
+fn adds_headers_to_errors() {
+    let p = ProjectBuilder::new("example");
+    p.build();
+    let st = Static::new(p.root()).set(Cors::new().allow_origin(AllowedOrigin::Any));
+
+    match request::get("http://localhost:3000/missing.json", from("https://anywhere.net"), &st) {
+        Ok(res) => panic!("Expected IronError, got Response: {}", res),
+        Err(e) => {
+            assert_eq!(e.response.status.unwrap(), Status::NotFound);
+            assert_eq!(raw(&e.response, "Access-Control-Allow-Origin"), Some("*"));
+        }
+    }
+}
+
+#[test]
+fn answers_preflight_requests() {
+    let p = ProjectBuilder::new("example").file("data.json", "{}");
+    p.build();
+    let cors = Cors::new()
+        .allow_origin(AllowedOrigin::Exact("https://app.example.com".to_owned()))
+        .allow_header("X-Requested-With")
+        .max_age(Duration::from_secs(600));
+    let st = Static::new(p.root()).set(cors);
+
+    let mut headers = from("https://app.example.com");
+    headers.set_raw("Access-Control-Request-Method", vec![b"GET".to_vec()]);
+    let res = request::options("http://localhost:3000/data.json", headers, &st).unwrap();
+    assert_eq!(res.status.unwrap(), Status::Ok);
+    assert_eq!(raw(&res, "Access-Control-Allow-Origin"), Some("https://app.example.com"));
+    assert_eq!(raw(&res, "Access-Control-Allow-Methods"), Some("GET, HEAD, OPTIONS"));
+    assert_eq!(raw(&res, "Access-Control-Allow-Headers"), Some("X-Requested-With"));
+    assert_eq!(raw(&res, "Access-Control-Max-Age"), Some("600"));
+
+    let mut headers = from("https://app.example.com");
+    headers.set_raw("Access-Control-Request-Method", vec![b"DELETE".to_vec()]);
+    let res = request::options("http://localhost:3000/data.json", headers, &st).unwrap();
+    assert_eq!(raw(&res, "Access-Control-Allow-Methods"), None);
+
+    let mut headers = from("https://evil.com");
+    headers.set_raw("Access-Control-Request-Method", vec![b"GET".to_vec()]);
+    let res = request::options("http://localhost:3000/data.json", headers, &st).unwrap();
+    assert_eq!(raw(&res, "Access-Control-Allow-Origin"), None);
+}
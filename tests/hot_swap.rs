@@ -0,0 +1,38 @@
+extern crate iron;
+extern crate iron_test;
+extern crate staticfile;
+
+use iron::Headers;
+use iron::prelude::*;
+use iron_test::{request, ProjectBuilder};
+
+use staticfile::{HotSwap, Static};
+
+fn body(res: Response) -> String {
+    let mut body = Vec::new();
+    res.body.unwrap().write_body(&mut body).unwrap();
+    String::from_utf8(body).unwrap()
+}
+
+#[test]
+fn serves_new_requests_with_the_swapped_handler() {
+    let p = ProjectBuilder::new("hot_swap")
+        .file("v1/index.html", "one")
+        .file("v2/index.html", "two");
+    p.build();
+
+    let site = HotSwap::new(Static::new(p.root().join("v1")));
+    let server = site.clone();
+    let res = request::get("http://localhost:3000/", Headers::new(), &server).unwrap();
+    assert_eq!(body(res), "one");
+
+    // A request that started before the swap keeps the handler it started with.
+    let in_flight = site.current();
+    let old = site.swap(Static::new(p.root().join("v2")));
+    assert_eq!(old.root, p.root().join("v1"));
+
+    let res = request::get("http://localhost:3000/", Headers::new(), &server).unwrap();
+    assert_eq!(body(res), "two");
+    let res = request::get("http://localhost:3000/", Headers::new(), &*in_flight).unwrap();
+    assert_eq!(body(res), "one");
+}
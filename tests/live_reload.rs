@@ -0,0 +1,83 @@
+extern crate iron;
+extern crate iron_test;
+extern crate mount;
+extern crate staticfile;
+
+use std::fs;
+use std::io::{Read, Write};
+use std::net::TcpStream;
+use std::time::Duration;
+
+use iron::headers::{ContentLength, ContentType};
+use iron::prelude::*;
+use iron::{Headers, Set};
+use iron_test::{request, response, ProjectBuilder};
+use mount::Mount;
+use staticfile::{LiveReload, Static, Watch};
+
+#[test]
+fn it_should_inject_the_script_into_html_pages() {
+    let p = ProjectBuilder::new("live_reload_inject")
+        .file("index.html", "<html><body><p>hi</p></BODY></html>")
+        .file("plain.html", "<p>no body</p>")
+        .file("site.css", "p {}");
+    p.build();
+    let mut mount = Mount::new();
+    mount.mount("/app", Static::new(p.root()).set(LiveReload::new()));
+
+    let res = request::get("http://localhost:3000/app/", Headers::new(), &mount).unwrap();
+    let length = res.headers.get::<ContentLength>().unwrap().0;
+    let body = response::extract_body_to_string(res);
+    assert_eq!(body.len() as u64, length);
+    assert!(body.starts_with("<html><body><p>hi</p><script>"), "{}", body);
+    assert!(body.ends_with("</script>\n</BODY></html>"), "{}", body);
+    assert!(body.contains("new EventSource(\"/app/__staticfile/live-reload\")"), "{}", body);
+
+    let res = request::head("http://localhost:3000/app/index.html", Headers::new(), &mount).unwrap();
+    assert_eq!(res.headers.get::<ContentLength>().unwrap().0, length);
+
+    let res = request::get("http://localhost:3000/app/plain.html", Headers::new(), &mount).unwrap();
+    assert!(response::extract_body_to_string(res).starts_with("<p>no body</p><script>"));
+
+    let res = request::get("http://localhost:3000/app/site.css", Headers::new(), &mount).unwrap();
+    assert_eq!(response::extract_body_to_string(res), "p {}");
+}
+
+#[test]
+fn it_should_push_events_when_files_change() {
+    let p = ProjectBuilder::new("live_reload_events")
+        .file("index.html", "<body></body>")
+        .file("css/site.css", "p {}");
+    p.build();
+    let watch = Watch::new().polling(true).debounce(Duration::from_millis(20)).poll_interval(Duration::from_millis(20));
+    let st = Static::new(p.root()).set(LiveReload::new().endpoint("/_events").watch(watch));
+    let listening = Iron::new(st).http("127.0.0.1:0").unwrap();
+    let address = listening.socket;
+    // Dropping a `Listening` waits for the server to stop, which it never does.
+    ::std::mem::forget(listening);
+
+    let mut stream = TcpStream::connect(address).unwrap();
+    stream.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+    stream.write_all(b"GET /_events HTTP/1.1\r\nHost: localhost\r\n\r\n").unwrap();
+
+    let mut received = String::new();
+    let mut read_until = |expected: &str| {
+        let mut buffer = [0; 1024];
+        while !received.contains(expected) {
+            let read = stream.read(&mut buffer).unwrap_or_else(|e| panic!("{} after {:?}", e, received));
+            assert!(read > 0, "connection closed after {:?}", received);
+            received.push_str(&String::from_utf8_lossy(&buffer[..read]));
+        }
+        received.clone()
+    };
+
+    let head = read_until("retry: 1000");
+    assert!(head.starts_with("HTTP/1.1 200 OK"));
+    assert!(head.contains(&format!("{}", ContentType("text/event-stream".parse().unwrap()))));
+
+    fs::write(p.root().join("css/site.css"), "p { color: red }").unwrap();
+    read_until("event: css\ndata: css/site.css\n\n");
+
+    fs::write(p.root().join("index.html"), "<body>changed</body>").unwrap();
+    read_until("event: reload\n");
+}
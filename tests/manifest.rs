@@ -0,0 +1,186 @@
+extern crate hyper;
+extern crate iron;
+extern crate iron_test;
+extern crate mount;
+extern crate staticfile;
+
+use std::fs;
+
+use hyper::header::{CacheControl, Location};
+use iron::Headers;
+use iron::prelude::*;
+use iron::status::Status;
+use iron_test::{request, response, ProjectBuilder};
+use mount::Mount;
+use staticfile::{AssetManifest, OutdatedAssets, Static};
+
+#[test]
+fn it_should_serve_files_from_their_hashed_urls() {
+    let p = ProjectBuilder::new("example")
+        .file("app.js", "go()")
+        .file("css/site.css", "p {}")
+        .file("LICENSE", "MIT");
+    p.build();
+
+    let manifest = AssetManifest::build(p.root()).unwrap();
+    let st = Static::new(p.root()).set(manifest.clone());
+
+    let script = manifest.asset_url("app.js").unwrap();
+    assert!(script.starts_with("/app.") && script.ends_with(".js") && script.len() == "/app.12345678.js".len());
+    assert_eq!(manifest.asset_url("/app.js"), Some(script.clone()));
+    assert!(manifest.asset_url("css/site.css").unwrap().starts_with("/css/site."));
+    assert!(manifest.asset_url("LICENSE").unwrap().starts_with("/LICENSE."));
+    assert_eq!(manifest.asset_url("missing.js"), None);
+    assert_eq!(manifest.urls().unwrap().len(), 3);
+
+    let res = request::get(&format!("http://localhost:3000{}", script), Headers::new(), &st).unwrap();
+    assert_eq!(res.headers.get::<CacheControl>().unwrap().to_string(), "public, max-age=31536000, immutable");
+    assert_eq!(response::extract_body_to_string(res), "go()");
+
+    // The original URL keeps working, without the immutable policy.
+    let res = request::get("http://localhost:3000/app.js", Headers::new(), &st).unwrap();
+    assert!(res.headers.get::<CacheControl>().is_none());
+    assert_eq!(response::extract_body_to_string(res), "go()");
+}
+
+#[test]
+fn it_should_refuse_or_redirect_outdated_hashes() {
+    let p = ProjectBuilder::new("example").file("js/app.js", "go()");
+    p.build();
+
+    let manifest = AssetManifest::on_demand(p.root());
+    let current = manifest.asset_url("js/app.js").unwrap();
+
+    let st = Static::new(p.root()).set(manifest.clone());
+    match request::get("http://localhost:3000/js/app.00000000.js", Headers::new(), &st) {
+        Ok(res) => panic!("Expected IronError, got Response: {}", res),
+        Err(e) => assert_eq!(e.response.status.unwrap(), Status::NotFound),
+    }
+
+    let mut mount = Mount::new();
+    mount.mount("/static/", Static::new(p.root()).set(manifest.outdated(OutdatedAssets::Redirect)));
+    let res = request::get("http://localhost:3000/static/js/app.00000000.js", Headers::new(), &mount).unwrap();
+    assert_eq!(res.status.unwrap(), Status::Found);
+    assert_eq!(res.headers.get::<Location>().unwrap().0, format!("http://localhost:3000/static{}", current));
+}
+
+#[test]
+fn it_should_pick_up_changes_on_rebuild() {
+    let p = ProjectBuilder::new("example").file("app.js", "go()");
+    p.build();
+
+    let manifest = AssetManifest::build(p.root()).unwrap();
+    let old = manifest.asset_url("app.js").unwrap();
+
+    fs::write(p.root().join("app.js"), "go(2)").unwrap();
+    assert_eq!(manifest.asset_url("app.js"), Some(old.clone()));
+    manifest.rebuild().unwrap();
+    assert_ne!(manifest.asset_url("app.js"), Some(old));
+}
+
+#[test]
+fn it_should_check_access_before_answering_outdated_hashes() {
+    use std::path::Path;
+    use staticfile::Access;
+
+    let p = ProjectBuilder::new("example")
+        .file("vault/key.txt", "secret")
+        .file("app.js.map", "{}");
+    p.build();
+
+    let manifest = AssetManifest::build(p.root()).unwrap().outdated(OutdatedAssets::Redirect);
+    let st = Static::new(p.root())
+        .set(manifest)
+        .deny("**/*.map")
+        .authorizer(|_: &Request, path: &Path| {
+            if path.starts_with("vault") { Access::Deny } else { Access::Allow }
+        });
+
+    let e = request::get("http://localhost:3000/vault/key.00000000.txt", Headers::new(), &st).unwrap_err();
+    assert_eq!(e.response.status.unwrap(), Status::Forbidden);
+    assert!(e.response.headers.get::<Location>().is_none());
+
+    let e = request::get("http://localhost:3000/app.js.00000000.map", Headers::new(), &st).unwrap_err();
+    assert_eq!(e.response.status.unwrap(), Status::NotFound);
+    assert!(e.response.headers.get::<Location>().is_none());
+}
+
+#[cfg(feature = "auth")]
+#[test]
+fn it_should_challenge_outdated_hashes_of_protected_files() {
+    use staticfile::{BasicAuth, Htpasswd};
+
+    let p = ProjectBuilder::new("example").file("internal/plans.txt", "plans");
+    p.build();
+
+    let users = Htpasswd::parse("alice:{SHA}W6ph5Mm5Pz8GgiULbPgzG37mj9g=").unwrap();
+    let manifest = AssetManifest::build(p.root()).unwrap().outdated(OutdatedAssets::Redirect);
+    let st = Static::new(p.root()).set(manifest).basic_auth("internal", BasicAuth::new("Staff", users));
+
+    let e = request::get("http://localhost:3000/internal/plans.00000000.txt", Headers::new(), &st).unwrap_err();
+    assert_eq!(e.response.status.unwrap(), Status::Unauthorized);
+    assert!(e.response.headers.get::<Location>().is_none());
+}
+
+#[cfg(feature = "cache")]
+#[test]
+fn it_should_keep_private_policies_of_hashed_urls() {
+    use std::time::Duration;
+    use staticfile::{Cache, CachePolicy};
+
+    let p = ProjectBuilder::new("example")
+        .file("private/report.csv", "a,b")
+        .file("app.js", "go()");
+    p.build();
+
+    let manifest = AssetManifest::build(p.root()).unwrap();
+    let cache = Cache::new(Duration::from_secs(60))
+        .glob("/private/**", CachePolicy::new().private().no_store());
+    let st = Static::new(p.root()).set(manifest.clone()).set(cache);
+
+    let report = manifest.asset_url("private/report.csv").unwrap();
+    let res = request::get(&format!("http://localhost:3000{}", report), Headers::new(), &st).unwrap();
+    assert_eq!(res.headers.get::<CacheControl>().unwrap().to_string(), "private, no-store, max-age=31536000, immutable");
+
+    let script = manifest.asset_url("app.js").unwrap();
+    let res = request::get(&format!("http://localhost:3000{}", script), Headers::new(), &st).unwrap();
+    assert_eq!(res.headers.get::<CacheControl>().unwrap().to_string(), "public, max-age=31536000, immutable");
+}
+
+#[cfg(unix)]
+#[test]
+fn it_should_skip_entries_it_cannot_hash() {
+    use std::os::unix::fs::symlink;
+
+    let p = ProjectBuilder::new("example")
+        .file("app.js", "go()")
+        .file("lib/util.js", "util()");
+    p.build();
+    symlink(p.root().join("missing.js"), p.root().join("dangling.js")).unwrap();
+    symlink(p.root().join("missing"), p.root().join("lib/dangling")).unwrap();
+
+    let manifest = AssetManifest::build(p.root()).unwrap();
+    assert_eq!(manifest.urls().unwrap().len(), 2);
+    assert_eq!(manifest.asset_url("dangling.js"), None);
+
+    let on_demand = AssetManifest::on_demand(p.root());
+    let st = Static::new(p.root()).set(on_demand.clone());
+    let script = on_demand.asset_url("lib/util.js").unwrap();
+    let res = request::get(&format!("http://localhost:3000{}", script), Headers::new(), &st).unwrap();
+    assert_eq!(response::extract_body_to_string(res), "util()");
+}
+
+#[cfg(unix)]
+#[test]
+fn it_should_not_follow_symlink_loops() {
+    use std::os::unix::fs::symlink;
+
+    let p = ProjectBuilder::new("example")
+        .file("a/x.js", "x()");
+    p.build();
+    symlink("..", p.root().join("a/up")).unwrap();
+    symlink("..", p.root().join("a/up2")).unwrap();
+
+    let manifest = AssetManifest::build(p.root()).unwrap();
+    assert_eq!(manifest.urls().unwrap().keys().collect::<Vec<_>>(), ["/a/x.js"]);
+}
@@ -0,0 +1,128 @@
+#[cfg(feature = "gzip")]
+extern crate hyper;
+extern crate iron;
+extern crate iron_test;
+extern crate staticfile;
+
+use iron::headers::{ByteRangeSpec, ContentType, IfNoneMatch, Range};
+use iron::Headers;
+use iron_test::{request, response, ProjectBuilder};
+use staticfile::{Metrics, MetricsRegistry, Static};
+
+fn lines(registry: &MetricsRegistry) -> Vec<String> {
+    registry.render().lines().map(str::to_owned).collect()
+}
+
+#[test]
+fn it_should_count_requests_once_they_are_sent() {
+    let p = ProjectBuilder::new("metrics").file("hello.txt", "hello");
+    p.build();
+    let registry = MetricsRegistry::new();
+    let st = Static::new(p.root()).metrics(registry.clone());
+
+    let res = request::get("http://localhost:3000/hello.txt", Headers::new(), &st).unwrap();
+    assert!(!registry.render().contains("staticfile_requests_total"));
+    assert_eq!(response::extract_body_to_string(res), "hello");
+
+    let mut headers = Headers::new();
+    headers.set(Range::Bytes(vec![ByteRangeSpec::AllFrom(2)]));
+    response::extract_body_to_string(request::get("http://localhost:3000/hello.txt", headers, &st).unwrap());
+    let mut headers = Headers::new();
+    headers.set(IfNoneMatch::Any);
+    drop(request::get("http://localhost:3000/hello.txt", headers, &st).unwrap());
+    drop(request::get("http://localhost:3000/missing.txt", Headers::new(), &st).unwrap_err());
+
+    let lines = lines(&registry);
+    for expected in &[
+        "# TYPE staticfile_requests_total counter",
+        "staticfile_requests_total{class=\"2xx\"} 2",
+        "staticfile_requests_total{class=\"3xx\"} 1",
+        "staticfile_requests_total{class=\"4xx\"} 1",
+        "staticfile_sent_bytes_total 10",
+        "staticfile_not_modified_total 1",
+        "staticfile_range_requests_total 1",
+        "# TYPE staticfile_request_duration_seconds histogram",
+        "staticfile_request_duration_seconds_bucket{le=\"+Inf\"} 4",
+        "staticfile_request_duration_seconds_count 4",
+    ] {
+        assert!(lines.iter().any(|line| line == expected), "{} missing from {:#?}", expected, lines);
+    }
+    assert!(!lines.iter().any(|line| line.starts_with("staticfile_cache_")));
+}
+
+#[test]
+fn it_should_render_histograms_cumulatively() {
+    let registry = MetricsRegistry::new().buckets("size", &[10.0, 1.0]);
+    registry.histogram("size", &[("kind", "a \"b\"")], 0.5);
+    registry.histogram("size", &[("kind", "a \"b\"")], 5.0);
+    registry.histogram("size", &[("kind", "a \"b\"")], 50.0);
+    registry.counter("files", &[], 2);
+    registry.counter("files", &[], 3);
+
+    assert_eq!(registry.render(), "\
+# TYPE files counter
+files 5
+# TYPE size histogram
+size_bucket{kind=\"a \\\"b\\\"\",le=\"1\"} 1
+size_bucket{kind=\"a \\\"b\\\"\",le=\"10\"} 2
+size_bucket{kind=\"a \\\"b\\\"\",le=\"+Inf\"} 3
+size_sum{kind=\"a \\\"b\\\"\"} 55.5
+size_count{kind=\"a \\\"b\\\"\"} 3
+");
+}
+
+#[test]
+fn it_should_serve_the_metrics_as_text() {
+    let registry = MetricsRegistry::new();
+    registry.counter("staticfile_sent_bytes_total", &[], 42);
+
+    let res = request::get("http://localhost:3000/metrics", Headers::new(), &registry).unwrap();
+    assert_eq!(res.headers.get::<ContentType>().unwrap().to_string(),
+               "text/plain; version=0.0.4; charset=utf-8");
+    assert_eq!(response::extract_body_to_string(res),
+               "# TYPE staticfile_sent_bytes_total counter\nstaticfile_sent_bytes_total 42\n");
+}
+
+#[cfg(feature = "gzip")]
+#[test]
+fn it_should_record_the_compression_ratio() {
+    use hyper::header::{AcceptEncoding, Encoding, qitem};
+    use iron::Set;
+    use staticfile::Compression;
+
+    let p = ProjectBuilder::new("metrics_gzip").file("big.txt", "all work and no play ".repeat(100));
+    p.build();
+    let registry = MetricsRegistry::new();
+    let st = Static::new(p.root()).metrics(registry.clone()).set(Compression::new());
+
+    let mut headers = Headers::new();
+    headers.set(AcceptEncoding(vec![qitem(Encoding::Gzip)]));
+    let res = request::get("http://localhost:3000/big.txt", headers, &st).unwrap();
+    let mut body = Vec::new();
+    res.body.unwrap().write_body(&mut body).unwrap();
+
+    let lines = lines(&registry);
+    assert!(lines.contains(&"staticfile_compressed_responses_total{encoding=\"gzip\"} 1".to_owned()));
+    assert!(lines.contains(&"staticfile_compression_ratio_bucket{encoding=\"gzip\",le=\"0.1\"} 1".to_owned()));
+    assert!(lines.contains(&"staticfile_compression_ratio_count{encoding=\"gzip\"} 1".to_owned()));
+}
+
+#[cfg(feature = "mmap")]
+#[test]
+fn it_should_count_cache_hits_and_misses() {
+    use iron::Set;
+    use staticfile::MmapCache;
+
+    let p = ProjectBuilder::new("metrics_mmap").file("hello.txt", "hello");
+    p.build();
+    let registry = MetricsRegistry::new();
+    let st = Static::new(p.root()).metrics(registry.clone()).set(unsafe { MmapCache::immutable_root(1) });
+
+    for _ in 0..3 {
+        response::extract_body_to_string(request::get("http://localhost:3000/hello.txt", Headers::new(), &st).unwrap());
+    }
+
+    let lines = lines(&registry);
+    assert!(lines.contains(&"staticfile_cache_misses_total 1".to_owned()));
+    assert!(lines.contains(&"staticfile_cache_hits_total 2".to_owned()));
+}
@@ -0,0 +1,119 @@
+extern crate iron;
+extern crate iron_test;
+extern crate staticfile;
+
+use iron::Headers;
+use iron::headers::ContentType;
+use iron::mime::Mime;
+use iron::prelude::*;
+use iron_test::{request, ProjectBuilder};
+use staticfile::{MimeTypes, Static};
+
+fn content_type<H: iron::Handler>(url: &str, handler: &H) -> Mime {
+    match request::get(url, Headers::new(), handler) {
+        Ok(res) => res.headers.get::<ContentType>().unwrap().0.clone(),
+        Err(e) => panic!("{}", e)
+    }
+}
+
+#[test]
+fn serves_modern_web_types() {
+    let p = ProjectBuilder::new("example")
+        .file("app.wasm", "")
+        .file("app.mjs", "")
+        .file("site.webmanifest", "")
+        .file("photo.AVIF", "");
+    p.build();
+    let st = Static::new(p.root());
+
+    assert_eq!(content_type("http://localhost:3000/app.wasm", &st), "application/wasm".parse::<Mime>().unwrap());
+    assert_eq!(content_type("http://localhost:3000/app.mjs", &st), "application/javascript".parse::<Mime>().unwrap());
+    assert_eq!(content_type("http://localhost:3000/site.webmanifest", &st), "application/manifest+json".parse::<Mime>().unwrap());
+    assert_eq!(content_type("http://localhost:3000/photo.AVIF", &st), "image/avif".parse::<Mime>().unwrap());
+}
+
+#[test]
+fn falls_back_to_the_default_type() {
+    let p = ProjectBuilder::new("example").file("LICENSE", "MIT");
+    p.build();
+
+    let st = Static::new(p.root());
+    assert_eq!(content_type("http://localhost:3000/LICENSE", &st), "text/plain".parse::<Mime>().unwrap());
+
+    let st = Static::new(p.root()).set(MimeTypes::new().default_type("application/octet-stream".parse().unwrap()));
+    assert_eq!(content_type("http://localhost:3000/LICENSE", &st), "application/octet-stream".parse::<Mime>().unwrap());
+}
+
+#[test]
+fn applies_extension_and_glob_overrides() {
+    let p = ProjectBuilder::new("example")
+        .file("page.tpl", "")
+        .file("downloads/page.html", "")
+        .file("downloads/nested/page.html", "");
+    p.build();
+    let types = MimeTypes::new()
+        .extension(".tpl", "text/html".parse().unwrap())
+        .glob("/downloads/*", "application/octet-stream".parse().unwrap());
+    let st = Static::new(p.root()).set(types);
+
+    assert_eq!(content_type("http://localhost:3000/page.tpl", &st), "text/html".parse::<Mime>().unwrap());
+    assert_eq!(content_type("http://localhost:3000/downloads/page.html", &st), "application/octet-stream".parse::<Mime>().unwrap());
+    assert_eq!(content_type("http://localhost:3000/downloads/nested/page.html", &st), "text/html".parse::<Mime>().unwrap());
+}
+
+#[test]
+fn sniffs_files_without_a_known_extension() {
+    let p = ProjectBuilder::new("example")
+        .file("blobs/3f9a2c", &b"\x89PNG\r\n\x1a\n\x00\x00\x00\rIHDR"[..])
+        .file("blobs/7c01aa", &b"%PDF-1.7\n"[..])
+        .file("blobs/0d4e11", &b"\x00\x01\x02\x03"[..])
+        .file("LICENSE", "Permission is hereby granted, free of charge");
+    p.build();
+    let types = MimeTypes::new().sniff(true).default_type("application/octet-stream".parse().unwrap());
+    let st = Static::new(p.root()).set(types);
+
+    assert_eq!(content_type("http://localhost:3000/blobs/3f9a2c", &st), "image/png".parse::<Mime>().unwrap());
+    assert_eq!(content_type("http://localhost:3000/blobs/7c01aa", &st), "application/pdf".parse::<Mime>().unwrap());
+    assert_eq!(content_type("http://localhost:3000/blobs/0d4e11", &st), "application/octet-stream".parse::<Mime>().unwrap());
+    assert_eq!(content_type("http://localhost:3000/LICENSE", &st), "text/plain".parse::<Mime>().unwrap());
+
+    let head = request::head("http://localhost:3000/blobs/3f9a2c", Headers::new(), &st).unwrap();
+    assert_eq!(head.headers.get::<ContentType>().unwrap().0, "image/png".parse::<Mime>().unwrap());
+
+    let res = request::get("http://localhost:3000/blobs/3f9a2c", Headers::new(), &st).unwrap();
+    assert_eq!(res.headers.get_raw("X-Content-Type-Options"), Some(&[b"nosniff".to_vec()][..]));
+    let mut body = Vec::new();
+    res.body.unwrap().write_body(&mut body).unwrap();
+    assert_eq!(&body[..4], b"\x89PNG");
+}
+
+#[test]
+fn prefers_the_extension_over_sniffing() {
+    let p = ProjectBuilder::new("example").file("data.json", &b"\x89PNG\r\n\x1a\n"[..]);
+    p.build();
+    let st = Static::new(p.root()).set(MimeTypes::new().sniff(true));
+
+    assert_eq!(content_type("http://localhost:3000/data.json", &st), "application/json".parse::<Mime>().unwrap());
+}
+
+#[test]
+fn appends_charsets_to_textual_types() {
+    let p = ProjectBuilder::new("example")
+        .file("index.html", "<p>héllo</p>")
+        .file("app.js", "")
+        .file("legacy.txt", "")
+        .file("bom.css", &b"\xff\xfeb\x00"[..])
+        .file("logo.png", "");
+    p.build();
+    let types = MimeTypes::new()
+        .default_charset("utf-8")
+        .charset("txt", "iso-8859-1")
+        .detect_bom(true);
+    let st = Static::new(p.root()).set(types);
+
+    assert_eq!(content_type("http://localhost:3000/index.html", &st), "text/html; charset=utf-8".parse::<Mime>().unwrap());
+    assert_eq!(content_type("http://localhost:3000/app.js", &st), "application/javascript; charset=utf-8".parse::<Mime>().unwrap());
+    assert_eq!(content_type("http://localhost:3000/legacy.txt", &st), "text/plain; charset=iso-8859-1".parse::<Mime>().unwrap());
+    assert_eq!(content_type("http://localhost:3000/bom.css", &st), "text/css; charset=utf-16le".parse::<Mime>().unwrap());
+    assert_eq!(content_type("http://localhost:3000/logo.png", &st), "image/png".parse::<Mime>().unwrap());
+}
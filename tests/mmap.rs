@@ -0,0 +1,90 @@
+extern crate filetime;
+extern crate iron;
+extern crate iron_test;
+extern crate staticfile;
+
+#[cfg(feature = "mmap")]
+mod mmap {
+    use std::fs::{self, File};
+    use std::io::Write;
+
+    use filetime::{self, FileTime};
+    use iron::Headers;
+    use iron::headers::ContentLength;
+    use iron::prelude::*;
+    use iron_test::{request, ProjectBuilder};
+    use staticfile::{MmapCache, Static};
+
+    fn body(res: Response) -> String {
+        let mut body = Vec::new();
+        res.body.unwrap().write_body(&mut body).unwrap();
+        String::from_utf8(body).unwrap()
+    }
+
+    #[test]
+    fn it_should_serve_files_from_a_shared_mapping() {
+        let p = ProjectBuilder::new("example").file("file1.html", "this is file1");
+        p.build();
+
+        let mmap = unsafe { MmapCache::immutable_root(1) };
+        let st = Static::new(p.root()).set(mmap.clone());
+
+        for _ in 0..2 {
+            let res = request::get("http://localhost:3000/file1.html", Headers::new(), &st).unwrap();
+            assert_eq!(res.headers.get::<ContentLength>(), Some(&ContentLength(13)));
+            assert_eq!(body(res), "this is file1");
+        }
+        assert_eq!(mmap.len(), 1);
+    }
+
+    #[test]
+    fn it_should_not_map_files_below_the_minimum_size() {
+        let p = ProjectBuilder::new("example").file("file1.html", "this is file1");
+        p.build();
+
+        let mmap = unsafe { MmapCache::immutable_root(1024) };
+        let st = Static::new(p.root()).set(mmap.clone());
+
+        let res = request::get("http://localhost:3000/file1.html", Headers::new(), &st).unwrap();
+        assert_eq!(body(res), "this is file1");
+        assert!(mmap.is_empty());
+    }
+
+    #[test]
+    fn it_should_remap_files_replaced_by_a_rename() {
+        let p = ProjectBuilder::new("example").file("file1.html", "this is file1");
+        p.build();
+
+        let st = Static::new(p.root()).set(unsafe { MmapCache::immutable_root(1) });
+        let res = request::get("http://localhost:3000/file1.html", Headers::new(), &st).unwrap();
+        assert_eq!(body(res), "this is file1");
+
+        let staged = p.root().join("file1.html.new");
+        File::create(&staged).unwrap().write_all(b"this is the new file1").unwrap();
+        fs::rename(&staged, p.root().join("file1.html")).unwrap();
+
+        let res = request::get("http://localhost:3000/file1.html", Headers::new(), &st).unwrap();
+        assert_eq!(body(res), "this is the new file1");
+    }
+
+    #[test]
+    fn it_should_remap_files_replaced_with_the_same_size_and_time() {
+        let p = ProjectBuilder::new("example").file("file1.html", "this is file1");
+        p.build();
+        let path = p.root().join("file1.html");
+
+        let st = Static::new(p.root()).set(unsafe { MmapCache::immutable_root(1) });
+        let res = request::get("http://localhost:3000/file1.html", Headers::new(), &st).unwrap();
+        assert_eq!(body(res), "this is file1");
+
+        // As `rsync -t` or `cp -p` would leave it.
+        let staged = p.root().join("file1.html.new");
+        File::create(&staged).unwrap().write_all(b"this is file2").unwrap();
+        let mtime = FileTime::from_last_modification_time(&fs::metadata(&path).unwrap());
+        filetime::set_file_times(&staged, mtime, mtime).unwrap();
+        fs::rename(&staged, &path).unwrap();
+
+        let res = request::get("http://localhost:3000/file1.html", Headers::new(), &st).unwrap();
+        assert_eq!(body(res), "this is file2");
+    }
+}
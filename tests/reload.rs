@@ -0,0 +1,130 @@
+extern crate filetime;
+extern crate iron;
+extern crate iron_test;
+#[cfg(all(feature = "reload", unix))]
+extern crate signal_hook;
+extern crate staticfile;
+
+#[cfg(feature = "reload")]
+mod reload {
+    use std::fs;
+    use std::path::Path;
+    use std::sync::{Arc, Mutex};
+    use std::thread;
+    use std::time::{Duration, Instant};
+
+    use filetime::{self, FileTime};
+    use iron::{Handler, Headers};
+    use iron_test::{request, ProjectBuilder};
+    use staticfile::ConfigReloader;
+
+    fn project(name: &str) -> ProjectBuilder {
+        let p = ProjectBuilder::new(name)
+            .file("v1/index.html", "one")
+            .file("v2/index.html", "two");
+        p.build();
+        p
+    }
+
+    fn write_config(p: &ProjectBuilder, root: &str) {
+        let config = format!("[[mount]]\nroot = \"{}/{}\"\n", p.root().display(), root);
+        fs::write(p.root().join("staticfile.toml"), config).unwrap();
+    }
+
+    /// Move the file's modification time forward, so a change is seen even within a second.
+    fn touch(path: &Path, seconds: u64) {
+        let mtime = FileTime::from_last_modification_time(&fs::metadata(path).unwrap());
+        let later = FileTime::from_seconds_since_1970(mtime.seconds_relative_to_1970() + seconds, 0);
+        filetime::set_file_times(path, later, later).unwrap();
+    }
+
+    fn body<H: Handler>(handler: &H) -> String {
+        let res = request::get("http://localhost:3000/", Headers::new(), handler).unwrap();
+        let mut body = Vec::new();
+        res.body.unwrap().write_body(&mut body).unwrap();
+        String::from_utf8(body).unwrap()
+    }
+
+    fn eventually<F: Fn() -> bool>(condition: F) -> bool {
+        let start = Instant::now();
+        while start.elapsed() < Duration::from_secs(5) {
+            if condition() {
+                return true;
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+        false
+    }
+
+    #[test]
+    fn reloads_and_keeps_serving_the_old_settings_if_the_file_is_invalid() {
+        let p = project("reload");
+        write_config(&p, "v1");
+        let config = ConfigReloader::new(p.root().join("staticfile.toml")).unwrap();
+        let handler = config.handler();
+        assert_eq!(body(&handler), "one");
+
+        let in_flight = handler.current();
+        write_config(&p, "v2");
+        config.reload().unwrap();
+        assert_eq!(body(&handler), "two");
+        assert_eq!(body(&*in_flight), "one");
+
+        fs::write(p.root().join("staticfile.toml"), "[[mount]]\nroot = 42\n").unwrap();
+        assert!(config.reload().is_err());
+        assert_eq!(body(&handler), "two");
+    }
+
+    #[test]
+    fn reloads_when_the_file_changes() {
+        let p = project("reload_watch");
+        let path = p.root().join("staticfile.toml");
+        write_config(&p, "v1");
+        let errors = Arc::new(Mutex::new(Vec::new()));
+        let reported = errors.clone();
+        let config = ConfigReloader::with_error_handler(&path, move |_, e| {
+            reported.lock().unwrap().push(e.to_string());
+        }).unwrap();
+        let _watch = config.watch(Duration::from_millis(10)).unwrap();
+        let handler = config.handler();
+
+        write_config(&p, "v2");
+        touch(&path, 10);
+        assert!(eventually(|| body(&handler) == "two"));
+
+        fs::write(&path, "[[mount]]\nroot = 42\n").unwrap();
+        touch(&path, 20);
+        assert!(eventually(|| errors.lock().unwrap().len() == 1));
+        assert_eq!(body(&handler), "two");
+    }
+
+    #[test]
+    fn stops_watching_when_dropped() {
+        let p = project("reload_stop");
+        let path = p.root().join("staticfile.toml");
+        write_config(&p, "v1");
+        let config = ConfigReloader::new(&path).unwrap();
+        let handler = config.handler();
+
+        drop(config.watch(Duration::from_millis(10)).unwrap());
+        write_config(&p, "v2");
+        touch(&path, 10);
+        thread::sleep(Duration::from_millis(100));
+        assert_eq!(body(&handler), "one");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn reloads_on_sighup() {
+        let p = project("reload_sighup");
+        write_config(&p, "v1");
+        let config = ConfigReloader::new(p.root().join("staticfile.toml")).unwrap();
+        let sighup = config.reload_on_sighup().unwrap();
+        let handler = config.handler();
+
+        write_config(&p, "v2");
+        ::signal_hook::low_level::raise(::signal_hook::consts::SIGHUP).unwrap();
+        assert!(eventually(|| body(&handler) == "two"));
+        drop(sighup);
+    }
+}
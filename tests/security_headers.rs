@@ -0,0 +1,89 @@
+extern crate iron;
+extern crate iron_test;
+extern crate staticfile;
+
+use iron::Headers;
+use iron::prelude::*;
+use iron::status::Status;
+use iron_test::{request, ProjectBuilder};
+use staticfile::{SecurityHeaders, Static};
+
+fn raw<'a>(res: &'a Response, name: &str) -> Option<&'a str> {
+    res.headers.get_raw(name).map(|values| std::str::from_utf8(&values[0]).unwrap())
+}
+
+#[test]
+fn it_should_send_the_preset_headers() {
+    let p = ProjectBuilder::new("example").file("index.html", "<p>hi</p>").file("app.js", "go()");
+    p.build();
+    let st = Static::new(p.root()).set(SecurityHeaders::strict());
+
+    let res = request::get("http://localhost:3000/index.html", Headers::new(), &st).unwrap();
+    assert_eq!(raw(&res, "X-Content-Type-Options"), Some("nosniff"));
+    assert_eq!(raw(&res, "X-Frame-Options"), Some("DENY"));
+    assert_eq!(raw(&res, "Referrer-Policy"), Some("no-referrer"));
+    assert_eq!(raw(&res, "Strict-Transport-Security"), Some("max-age=31536000; includeSubDomains"));
+    assert!(raw(&res, "Permissions-Policy").is_some());
+    assert!(raw(&res, "Content-Security-Policy").unwrap().starts_with("default-src 'self'"));
+
+    // The policy is only meant for documents.
+    let res = request::get("http://localhost:3000/app.js", Headers::new(), &st).unwrap();
+    assert_eq!(raw(&res, "X-Frame-Options"), Some("DENY"));
+    assert_eq!(raw(&res, "Content-Security-Policy"), None);
+}
+
+#[test]
+fn it_should_apply_rules_in_order() {
+    let p = ProjectBuilder::new("example")
+        .file("index.html", "<p>hi</p>")
+        .file("embed/player.html", "<video></video>")
+        .file("img/logo.png", "\u{89}PNG");
+    p.build();
+    let headers = SecurityHeaders::new()
+        .frame_options("DENY")
+        .mime("text/*", "Content-Security-Policy", Some("default-src 'self'"))
+        .glob("/embed/**", "X-Frame-Options", None)
+        .glob("/embed/**", "Content-Security-Policy", Some("frame-ancestors *"))
+        .mime("image/*", "Cross-Origin-Resource-Policy", Some("cross-origin"));
+    let st = Static::new(p.root()).set(headers);
+
+    let res = request::get("http://localhost:3000/index.html", Headers::new(), &st).unwrap();
+    assert_eq!(raw(&res, "X-Frame-Options"), Some("DENY"));
+    assert_eq!(raw(&res, "Content-Security-Policy"), Some("default-src 'self'"));
+
+    let res = request::get("http://localhost:3000/embed/player.html", Headers::new(), &st).unwrap();
+    assert_eq!(raw(&res, "X-Frame-Options"), None);
+    assert_eq!(raw(&res, "Content-Security-Policy"), Some("frame-ancestors *"));
+
+    let res = request::get("http://localhost:3000/img/logo.png", Headers::new(), &st).unwrap();
+    assert_eq!(raw(&res, "Cross-Origin-Resource-Policy"), Some("cross-origin"));
+    assert_eq!(raw(&res, "Content-Security-Policy"), None);
+}
+
+#[test]
+fn it_should_send_the_headers_with_listings() {
+    let p = ProjectBuilder::new("example").file("docs/notes.txt", "notes");
+    p.build();
+    let headers = SecurityHeaders::recommended()
+        .mime("text/html", "Content-Security-Policy", Some("default-src 'self'"));
+    let st = Static::new(p.root()).autoindex(true).set(headers);
+
+    let res = request::get("http://localhost:3000/docs/", Headers::new(), &st).unwrap();
+    assert_eq!(raw(&res, "X-Frame-Options"), Some("SAMEORIGIN"));
+    assert_eq!(raw(&res, "Content-Security-Policy"), Some("default-src 'self'"));
+}
+
+#[test]
+fn it_should_not_send_the_headers_with_errors() {
+    let p = ProjectBuilder::new("example");
+    p.build();
+    let st = Static::new(p.root()).set(SecurityHeaders::recommended());
+
+    match request::get("http://localhost:3000/missing.html", Headers::new(), &st) {
+        Ok(res) => panic!("Expected IronError, got Response: {}", res),
+        Err(e) => {
+            assert_eq!(e.response.status.unwrap(), Status::NotFound);
+            assert_eq!(raw(&e.response, "X-Frame-Options"), None);
+        }
+    }
+}
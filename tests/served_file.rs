@@ -0,0 +1,93 @@
+#[cfg(feature = "gzip")]
+extern crate hyper;
+extern crate iron;
+extern crate iron_test;
+extern crate staticfile;
+
+use iron::headers::Headers;
+use iron::mime::{Mime, SubLevel, TopLevel};
+use iron::prelude::*;
+use iron::{status, AfterMiddleware};
+use iron_test::{request, ProjectBuilder};
+use staticfile::{ServedFile, Static};
+
+/// Reports the file `Static` served, as downstream middleware sees it.
+struct Report;
+
+impl AfterMiddleware for Report {
+    fn after(&self, req: &mut Request, mut res: Response) -> IronResult<Response> {
+        let from_request = req.extensions.get::<ServedFile>().map(|file| file.path.clone());
+        let from_response = res.extensions.get::<ServedFile>().map(|file| file.path.clone());
+        assert_eq!(from_request, from_response);
+        if let Some(path) = from_response {
+            res.headers.set_raw("X-Served-File", vec![path.to_string_lossy().into_owned().into_bytes()]);
+        }
+        Ok(res)
+    }
+
+    fn catch(&self, req: &mut Request, err: IronError) -> IronResult<Response> {
+        assert!(req.extensions.get::<ServedFile>().is_none());
+        assert!(err.response.extensions.get::<ServedFile>().is_none());
+        Err(err)
+    }
+}
+
+#[test]
+fn it_should_describe_the_served_file() {
+    let p = ProjectBuilder::new("served_file").file("docs/guide.html", "<h1>Guide</h1>");
+    p.build();
+    let st = Static::new(p.root());
+
+    let res = request::get("http://localhost:3000/docs/guide.html", Headers::new(), &st).unwrap();
+    let file = res.extensions.get::<ServedFile>().unwrap();
+    assert_eq!(file.path, p.root().join("docs/guide.html"));
+    assert!(file.metadata.is_file());
+    assert_eq!(file.metadata.len(), 14);
+    assert_eq!(file.mime, Mime(TopLevel::Text, SubLevel::Html, vec![]));
+    assert_eq!(file.encoding, None);
+}
+
+#[test]
+fn it_should_share_the_served_file_with_middleware() {
+    let p = ProjectBuilder::new("served_file_chain")
+        .file("index.html", "home")
+        .file("docs/guide.html", "guide");
+    p.build();
+    let mut chain = Chain::new(Static::new(p.root()).autoindex(true));
+    chain.link_after(Report);
+
+    let res = request::get("http://localhost:3000/", Headers::new(), &chain).unwrap();
+    let served = p.root().join("index.html").to_string_lossy().into_owned();
+    assert_eq!(res.headers.get_raw("X-Served-File"), Some(&[served.into_bytes()][..]));
+
+    let res = request::head("http://localhost:3000/docs/guide.html", Headers::new(), &chain).unwrap();
+    assert!(res.headers.get_raw("X-Served-File").is_some());
+
+    // Listings and redirects don't serve a file.
+    let res = request::get("http://localhost:3000/docs/", Headers::new(), &chain).unwrap();
+    assert_eq!(res.status, Some(status::Ok));
+    assert!(res.headers.get_raw("X-Served-File").is_none());
+    let res = request::get("http://localhost:3000/docs", Headers::new(), &chain).unwrap();
+    assert_eq!(res.status, Some(status::MovedPermanently));
+    assert!(res.headers.get_raw("X-Served-File").is_none());
+
+    let err = request::get("http://localhost:3000/missing.html", Headers::new(), &chain).unwrap_err();
+    assert_eq!(err.response.status, Some(status::NotFound));
+}
+
+#[cfg(feature = "gzip")]
+#[test]
+fn it_should_record_the_chosen_encoding() {
+    use hyper::header::{AcceptEncoding, Encoding, qitem};
+    use iron::Set;
+    use staticfile::Compression;
+
+    let p = ProjectBuilder::new("served_file_gzip").file("big.txt", "all work and no play ".repeat(100));
+    p.build();
+    let st = Static::new(p.root()).set(Compression::new());
+
+    let mut headers = Headers::new();
+    headers.set(AcceptEncoding(vec![qitem(Encoding::Gzip)]));
+    let res = request::get("http://localhost:3000/big.txt", headers, &st).unwrap();
+    assert_eq!(res.extensions.get::<ServedFile>().unwrap().encoding, Some(Encoding::Gzip));
+}
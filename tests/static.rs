@@ -1,9 +1,15 @@
+// `ProjectBuilder::root` returns a `&Path`, which these tests have always cloned.
+#![allow(noop_method_call)]
+
 extern crate hyper;
 extern crate iron;
 extern crate iron_test;
 extern crate staticfile;
 
-use iron::headers::{Headers, Location};
+use iron::headers::{Allow, ContentLength, ContentType, Headers, Location};
+use iron::method::Method;
+use iron::prelude::*;
+use iron::mime::{Mime, TopLevel, SubLevel};
 use iron::status::Status;
 
 use iron_test::{request, ProjectBuilder};
@@ -114,6 +120,19 @@ fn normalizes_percent_encoded_path() {
     }
 }
 
+#[test]
+fn refuses_percent_encoded_paths_that_are_not_utf8() {
+    let p = ProjectBuilder::new("example").file("file1.html", "this is file1");
+    p.build();
+    let st = Static::new(p.root());
+    for url in &["http://localhost:3000/%FF", "http://localhost:3000/%c3%28/file1.html", "http://localhost:3000/xxx/..%2f%ff"] {
+        match request::get(url, Headers::new(), &st) {
+            Ok(res) => panic!("Expected IronError, got Response: {}", res),
+            Err(e) => assert_eq!(e.response.status.unwrap(), Status::NotFound),
+        }
+    }
+}
+
 #[test]
 fn prevents_from_escaping_root() {
     let p = ProjectBuilder::new("example").file("file1.html", "this is file1");
@@ -148,3 +167,206 @@ fn prevents_from_escaping_root() {
     }
 
 }
+
+#[test]
+fn sets_length_and_type_of_index_file_for_directory() {
+    let p = ProjectBuilder::new("example").file("dir/index.html", "this is index");
+    p.build();
+    let st = Static::new(p.root());
+    match request::get("http://localhost:3000/dir/", Headers::new(), &st) {
+        Ok(res) => {
+            assert_eq!(res.headers.get::<ContentLength>(), Some(&ContentLength(13)));
+            assert_eq!(res.headers.get::<ContentType>(), Some(&ContentType(Mime(TopLevel::Text, SubLevel::Html, vec![]))));
+            let mut body = Vec::new();
+            res.body.unwrap().write_body(&mut body).unwrap();
+            assert_eq!(str::from_utf8(&body).unwrap(), "this is index");
+        },
+        Err(e) => panic!("{}", e)
+    }
+}
+
+#[test]
+fn answers_head_with_the_headers_of_get_and_no_body() {
+    let p = ProjectBuilder::new("example").file("dir/index.html", "this is index");
+    p.build();
+    let st = Static::new(p.root());
+
+    let get = request::get("http://localhost:3000/dir/", Headers::new(), &st).unwrap();
+    match request::head("http://localhost:3000/dir/", Headers::new(), &st) {
+        Ok(res) => {
+            assert_eq!(res.status.unwrap(), Status::Ok);
+            assert_eq!(res.headers.get::<ContentLength>(), Some(&ContentLength(13)));
+            assert_eq!(res.headers.get::<ContentType>(), get.headers.get::<ContentType>());
+            let mut body = Vec::new();
+            res.body.unwrap().write_body(&mut body).unwrap();
+            assert!(body.is_empty());
+        },
+        Err(e) => panic!("{}", e)
+    }
+}
+
+#[test]
+fn returns_404_for_head_if_file_not_found() {
+    let p = ProjectBuilder::new("example");
+    p.build();
+    let st = Static::new(p.root());
+    match request::head("http://localhost:3000/missing.html", Headers::new(), &st) {
+        Ok(res) => panic!("Expected IronError, got Response: {}", res),
+        Err(e) => assert_eq!(e.response.status.unwrap(), Status::NotFound)
+    }
+}
+
+#[test]
+fn refuses_other_methods_with_allow_header() {
+    let p = ProjectBuilder::new("example").file("file1.html", "this is file1");
+    p.build();
+    let st = Static::new(p.root());
+    match request::post("http://localhost:3000/file1.html", Headers::new(), "", &st) {
+        Ok(res) => panic!("Expected IronError, got Response: {}", res),
+        Err(e) => {
+            assert_eq!(e.response.status.unwrap(), Status::MethodNotAllowed);
+            assert_eq!(e.response.headers.get::<Allow>(),
+                       Some(&Allow(vec![Method::Get, Method::Head, Method::Options])));
+        }
+    }
+}
+
+#[test]
+fn answers_options_with_allowed_methods() {
+    let p = ProjectBuilder::new("example").file("file1.html", "this is file1");
+    p.build();
+    let st = Static::new(p.root());
+    match request::options("http://localhost:3000/file1.html", Headers::new(), &st) {
+        Ok(res) => {
+            assert_eq!(res.status.unwrap(), Status::Ok);
+            assert_eq!(res.headers.get::<Allow>(),
+                       Some(&Allow(vec![Method::Get, Method::Head, Method::Options])));
+        },
+        Err(e) => panic!("{}", e)
+    }
+}
+
+#[test]
+fn passes_other_methods_to_fallback_handler() {
+    let p = ProjectBuilder::new("example").file("file1.html", "this is file1");
+    p.build();
+    let st = Static::new(p.root()).method_fallback(|req: &mut Request| {
+        Ok(Response::with((Status::Accepted, format!("{}", req.method))))
+    });
+    match request::delete("http://localhost:3000/file1.html", Headers::new(), &st) {
+        Ok(res) => {
+            assert_eq!(res.status.unwrap(), Status::Accepted);
+            let mut body = Vec::new();
+            res.body.unwrap().write_body(&mut body).unwrap();
+            assert_eq!(str::from_utf8(&body).unwrap(), "DELETE");
+        },
+        Err(e) => panic!("{}", e)
+    }
+}
+
+#[test]
+fn serves_the_first_existing_index_file() {
+    let p = ProjectBuilder::new("example").file("docs/index.htm", "old index").file("docs/README", "readme");
+    p.build();
+    let st = Static::new(p.root()).index_files(&["index.html", "index.htm"]);
+    match request::get("http://localhost:3000/docs/", Headers::new(), &st) {
+        Ok(res) => {
+            let mut body = Vec::new();
+            res.body.unwrap().write_body(&mut body).unwrap();
+            assert_eq!(str::from_utf8(&body).unwrap(), "old index");
+        },
+        Err(e) => panic!("{}", e)
+    }
+}
+
+#[test]
+fn lists_directories_without_an_index() {
+    let p = ProjectBuilder::new("example")
+        .file("files/b <&>.txt", "b")
+        .file("files/a.txt", "a")
+        .file("files/.secret", "s")
+        .file("files/z/c.txt", "c");
+    p.build();
+
+    let st = Static::new(p.root());
+    match request::get("http://localhost:3000/files/", Headers::new(), &st) {
+        Ok(res) => panic!("Expected IronError, got Response: {}", res),
+        Err(e) => assert_eq!(e.response.status.unwrap(), Status::NotFound),
+    }
+
+    let st = Static::new(p.root()).autoindex(true);
+    let res = request::get("http://localhost:3000/files/", Headers::new(), &st).unwrap();
+    assert_eq!(res.headers.get::<ContentType>(), Some(&ContentType::html()));
+    let length = res.headers.get::<ContentLength>().unwrap().0;
+    let mut body = Vec::new();
+    res.body.unwrap().write_body(&mut body).unwrap();
+    let body = String::from_utf8(body).unwrap();
+    assert_eq!(body.len() as u64, length);
+
+    assert!(body.contains("<title>Index of /files/</title>"));
+    let z = body.find("<a href=\"z/\">z/</a>").unwrap();
+    let a = body.find("<a href=\"a.txt\">a.txt</a>").unwrap();
+    let b = body.find("<a href=\"b%20%3C&amp;%3E.txt\">b &lt;&amp;&gt;.txt</a>").unwrap();
+    assert!(z < a && a < b);
+    assert!(!body.contains(".secret"));
+
+    let res = request::head("http://localhost:3000/files/", Headers::new(), &st).unwrap();
+    assert_eq!(res.headers.get::<ContentLength>().unwrap().0, length);
+    let mut body = Vec::new();
+    res.body.unwrap().write_body(&mut body).unwrap();
+    assert!(body.is_empty());
+}
+
+#[test]
+fn serves_the_spa_fallback_for_unknown_paths() {
+    let p = ProjectBuilder::new("example").file("index.html", "app").file("app.js", "go()");
+    p.build();
+    let st = Static::new(p.root()).spa_fallback("index.html");
+
+    for path in &["users/42", "", "app.js"] {
+        let res = request::get(&format!("http://localhost:3000/{}", path), Headers::new(), &st).unwrap();
+        assert_eq!(res.status.unwrap(), Status::Ok);
+        let mut body = Vec::new();
+        res.body.unwrap().write_body(&mut body).unwrap();
+        let expected = if *path == "app.js" { "go()" } else { "app" };
+        assert_eq!(str::from_utf8(&body).unwrap(), expected);
+    }
+}
+
+#[test]
+fn redirects_configured_paths() {
+    let p = ProjectBuilder::new("example").file("new.html", "new");
+    p.build();
+    let st = Static::new(p.root()).redirect("/old.html", "/new.html", Status::PermanentRedirect);
+
+    let res = request::get("http://localhost:3000/old.html", Headers::new(), &st).unwrap();
+    assert_eq!(res.status.unwrap(), Status::PermanentRedirect);
+    assert_eq!(res.headers.get::<Location>(), Some(&Location("/new.html".to_owned())));
+
+    let res = request::get("http://localhost:3000/new.html", Headers::new(), &st).unwrap();
+    assert_eq!(res.status.unwrap(), Status::Ok);
+}
+
+#[test]
+fn hides_denied_files() {
+    let p = ProjectBuilder::new("example")
+        .file("files/app.js", "app")
+        .file("files/app.js.map", "map")
+        .file("files/.git/config", "secret");
+    p.build();
+    let st = Static::new(p.root()).autoindex(true).deny("**/*.map").deny("**/.git/**");
+
+    for path in &["files/app.js.map", "files/.git/config"] {
+        match request::get(&format!("http://localhost:3000/{}", path), Headers::new(), &st) {
+            Ok(res) => panic!("Expected IronError, got Response: {}", res),
+            Err(e) => assert_eq!(e.response.status.unwrap(), Status::NotFound),
+        }
+    }
+
+    let res = request::get("http://localhost:3000/files/", Headers::new(), &st).unwrap();
+    let mut body = Vec::new();
+    res.body.unwrap().write_body(&mut body).unwrap();
+    let body = String::from_utf8(body).unwrap();
+    assert!(body.contains("app.js\""));
+    assert!(!body.contains("app.js.map"));
+}
@@ -0,0 +1,80 @@
+extern crate hyper;
+extern crate iron;
+extern crate iron_test;
+extern crate staticfile;
+extern crate time;
+
+use hyper::header::{CacheControl, ETag, EntityTag, HttpDate, IfModifiedSince, IfNoneMatch, LastModified};
+use iron::Headers;
+use iron::prelude::*;
+use iron::status::Status;
+use iron_test::{request, ProjectBuilder};
+use staticfile::{Static, Validators};
+
+#[test]
+fn it_should_send_validators_by_default() {
+    let p = ProjectBuilder::new("example").file("file1.html", "this is file1");
+    p.build();
+
+    let st = Static::new(p.root());
+    let res = request::get("http://localhost:3000/file1.html", Headers::new(), &st).unwrap();
+
+    assert!(res.headers.get::<LastModified>().is_some());
+    assert!(res.headers.get::<ETag>().unwrap().weak);
+    assert!(res.headers.get::<CacheControl>().is_none());
+}
+
+#[test]
+fn it_should_answer_conditional_requests_by_default() {
+    let p = ProjectBuilder::new("example").file("file1.html", "this is file1");
+    p.build();
+
+    let st = Static::new(p.root());
+    let res = request::get("http://localhost:3000/file1.html", Headers::new(), &st).unwrap();
+    let etag = res.headers.get::<ETag>().unwrap().0.clone();
+
+    let mut headers = Headers::new();
+    headers.set(IfModifiedSince(HttpDate(time::now_utc())));
+    let res = request::get("http://localhost:3000/file1.html", headers, &st).unwrap();
+    assert_eq!(res.status.unwrap(), Status::NotModified);
+
+    let mut headers = Headers::new();
+    headers.set(IfNoneMatch::Items(vec![EntityTag::strong("other".to_owned()), etag.clone()]));
+    let res = request::get("http://localhost:3000/file1.html", headers, &st).unwrap();
+    assert_eq!(res.status.unwrap(), Status::NotModified);
+    assert_eq!(res.headers.get::<ETag>().unwrap().0, etag);
+
+    // A mismatched ETag wins over a matching date.
+    let mut headers = Headers::new();
+    headers.set(IfNoneMatch::Items(vec![EntityTag::weak("other".to_owned())]));
+    headers.set(IfModifiedSince(HttpDate(time::now_utc())));
+    let res = request::get("http://localhost:3000/file1.html", headers, &st).unwrap();
+    assert_eq!(res.status.unwrap(), Status::Ok);
+}
+
+#[test]
+fn it_should_let_each_validator_be_turned_off() {
+    let p = ProjectBuilder::new("example").file("file1.html", "this is file1");
+    p.build();
+
+    let st = Static::new(p.root()).set(Validators::new().last_modified(false));
+    let mut headers = Headers::new();
+    headers.set(IfModifiedSince(HttpDate(time::now_utc())));
+    let res = request::get("http://localhost:3000/file1.html", headers, &st).unwrap();
+    assert_eq!(res.status.unwrap(), Status::Ok);
+    assert!(res.headers.get::<LastModified>().is_none());
+    assert!(res.headers.get::<ETag>().is_some());
+
+    let st = Static::new(p.root()).set(Validators::new().etag(false));
+    let res = request::get("http://localhost:3000/file1.html", Headers::new(), &st).unwrap();
+    assert!(res.headers.get::<LastModified>().is_some());
+    assert!(res.headers.get::<ETag>().is_none());
+
+    let st = Static::new(p.root()).set(Validators::none());
+    let mut headers = Headers::new();
+    headers.set(IfModifiedSince(HttpDate(time::now_utc())));
+    let res = request::get("http://localhost:3000/file1.html", headers, &st).unwrap();
+    assert_eq!(res.status.unwrap(), Status::Ok);
+    assert!(res.headers.get::<LastModified>().is_none());
+    assert!(res.headers.get::<ETag>().is_none());
+}
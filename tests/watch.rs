@@ -0,0 +1,152 @@
+extern crate iron;
+extern crate iron_test;
+extern crate staticfile;
+
+use std::fs;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use iron::{Headers, Set};
+use iron_test::{request, ProjectBuilder};
+use staticfile::{AssetManifest, Static, Watch};
+
+fn eventually<F: Fn() -> bool>(condition: F) -> bool {
+    let start = Instant::now();
+    while start.elapsed() < Duration::from_secs(5) {
+        if condition() {
+            return true;
+        }
+        thread::sleep(Duration::from_millis(10));
+    }
+    false
+}
+
+fn recording(watch: Watch) -> (Watch, Arc<Mutex<Vec<PathBuf>>>) {
+    let changes = Arc::new(Mutex::new(Vec::new()));
+    let recorded = changes.clone();
+    let watch = watch.debounce(Duration::from_millis(20)).on_change(move |path| {
+        recorded.lock().unwrap().push(path.to_owned());
+    });
+    (watch, changes)
+}
+
+#[test]
+fn it_should_rehash_changed_files_by_scanning() {
+    let p = ProjectBuilder::new("watch_polling").file("app.js", "go()").file("css/site.css", "p {}");
+    p.build();
+    let manifest = AssetManifest::build(p.root()).unwrap();
+    let st = Static::new(p.root()).set(manifest.clone());
+
+    let (watch, changes) = recording(Watch::new().polling(true).poll_interval(Duration::from_millis(20)));
+    let watcher = st.watch(watch).unwrap();
+    assert!(watcher.is_polling());
+
+    let script = manifest.asset_url("app.js").unwrap();
+    let style = manifest.asset_url("css/site.css").unwrap();
+    fs::write(p.root().join("app.js"), "go(); go()").unwrap();
+    assert!(eventually(|| manifest.asset_url("app.js") != Some(script.clone())));
+    assert_eq!(manifest.asset_url("css/site.css"), Some(style));
+    assert_eq!(*changes.lock().unwrap(), vec![PathBuf::from("app.js")]);
+
+    // The old hashed URL is no longer served as current.
+    let res = request::get(&format!("http://localhost:3000{}", script), Headers::new(), &st);
+    assert!(res.is_err());
+
+    fs::remove_dir_all(p.root().join("css")).unwrap();
+    assert!(eventually(|| manifest.asset_url("css/site.css").is_none()));
+}
+
+#[test]
+fn it_should_stop_when_dropped() {
+    let p = ProjectBuilder::new("watch_drop").file("app.js", "go()");
+    p.build();
+    let (watch, changes) = recording(Watch::new().polling(true).poll_interval(Duration::from_millis(10)));
+    drop(Static::new(p.root()).watch(watch).unwrap());
+
+    fs::write(p.root().join("app.js"), "stop()").unwrap();
+    thread::sleep(Duration::from_millis(100));
+    assert!(changes.lock().unwrap().is_empty());
+}
+
+#[cfg(unix)]
+#[test]
+fn it_should_not_follow_symlink_loops() {
+    use std::os::unix::fs::symlink;
+
+    // Without the `watch` feature, both watches scan.
+    for &(name, polling) in &[("watch_loop_polling", true), ("watch_loop", false)] {
+        let p = ProjectBuilder::new(name).file("a/x.js", "x()");
+        p.build();
+        symlink("..", p.root().join("a/up")).unwrap();
+        symlink("..", p.root().join("a/up2")).unwrap();
+
+        let (watch, changes) = recording(Watch::new().polling(polling).poll_interval(Duration::from_millis(20)));
+        let _watcher = Static::new(p.root()).watch(watch).unwrap();
+        fs::write(p.root().join("a/x.js"), "x(); x()").unwrap();
+        assert!(eventually(|| !changes.lock().unwrap().is_empty()), "{}", name);
+    }
+}
+
+#[cfg(all(feature = "watch", target_os = "linux"))]
+mod inotify {
+    use std::fs;
+    use std::path::PathBuf;
+
+    use iron::Set;
+    use staticfile::{AssetManifest, Static, Watch};
+
+    use super::{eventually, recording};
+
+    #[test]
+    fn it_should_rehash_changed_files_with_inotify() {
+        let p = super::ProjectBuilder::new("watch_inotify").file("app.js", "go()");
+        p.build();
+        let manifest = AssetManifest::build(p.root()).unwrap();
+        let st = Static::new(p.root()).set(manifest.clone());
+
+        let (watch, changes) = recording(Watch::new());
+        let watcher = st.watch(watch).unwrap();
+        assert!(!watcher.is_polling());
+
+        // Files in directories created after the watch started are seen too.
+        fs::create_dir(p.root().join("js")).unwrap();
+        assert!(eventually(|| changes.lock().unwrap().contains(&PathBuf::from("js"))));
+        fs::write(p.root().join("js/lib.js"), "lib()").unwrap();
+        assert!(eventually(|| manifest.asset_url("js/lib.js").is_some()));
+
+        let script = manifest.asset_url("app.js").unwrap();
+        fs::write(p.root().join("app.js"), "go(); go()").unwrap();
+        assert!(eventually(|| manifest.asset_url("app.js").is_some_and(|url| url != script)));
+    }
+}
+
+#[cfg(feature = "mmap")]
+mod mmap {
+    use std::fs;
+
+    use iron::{Headers, Set};
+    use iron_test::{request, ProjectBuilder};
+    use staticfile::{MmapCache, Static, Watch};
+
+    use super::eventually;
+
+    #[test]
+    fn it_should_drop_the_mappings_of_replaced_files() {
+        let p = ProjectBuilder::new("watch_mmap").file("file1.html", "this is file1");
+        p.build();
+        let mmap = unsafe { MmapCache::immutable_root(1) };
+        let st = Static::new(p.root()).set(mmap.clone());
+        let _watcher = st.watch(Watch::new().debounce(::std::time::Duration::from_millis(20))
+                                    .poll_interval(::std::time::Duration::from_millis(20)))
+            .unwrap();
+
+        request::get("http://localhost:3000/file1.html", Headers::new(), &st).unwrap();
+        assert_eq!(mmap.len(), 1);
+
+        fs::write(p.root().join("file1.new"), "this is the new file1").unwrap();
+        fs::rename(p.root().join("file1.new"), p.root().join("file1.html")).unwrap();
+        assert!(eventually(|| mmap.is_empty()));
+    }
+}